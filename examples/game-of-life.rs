@@ -1,59 +1,53 @@
 #[macro_use(blight_main)]
 extern crate blight;
 
-use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
 
-use blight::core::BlightCore;
-use blight::systems::input::{Event, Keycode, MouseButton};
-use blight::systems::render::{Color, Rect, RenderComponent, RenderSystem, Texture, WINDOW_SIZE};
+use blight::core::{EngineConfig, EngineContext};
+use blight::systems::input::{Event, InputSystem, Keycode, MouseButton};
+use blight::systems::render::{
+    Color, Image, Rect, RenderComponent, RenderSystem, Tile, Tilemap, Tileset, WindowConfig,
+    WINDOW_SIZE,
+};
 use blight::Game;
 
 const PLAYGROUND_WIDTH: u32 = 49;
 const PLAYGROUND_HEIGHT: u32 = 40;
 const CELL_SIZE: u32 = WINDOW_SIZE.0 / PLAYGROUND_WIDTH;
 
-struct GameOfLife<'a> {
-    engine: *mut BlightCore<'a>,
-    cell_texture: Texture<'a>,
+struct GameOfLife {
     playground: [bool; (PLAYGROUND_WIDTH * PLAYGROUND_HEIGHT) as usize],
     playing: bool,
-    cells: BTreeMap<usize, RenderComponent<'a>>,
+    board: RenderComponent,
     last_update: Instant,
     game_speed: Duration,
 }
 
-impl<'a> Game<'a> for GameOfLife<'a> {
-    fn new(engine: *mut BlightCore<'a>) -> Self {
-        unsafe {
-            let renderer = (*engine).get_render();
-            (*renderer).set_background_color(Some(Color::RGB(255, 255, 255)));
-        }
+impl Game for GameOfLife {
+    fn init(ctx: &mut EngineContext) -> Self {
+        let renderer = ctx.get_render_mut();
+        renderer.set_background_color(Some(Color::RGB(255, 255, 255)));
         GameOfLife {
-            cell_texture: GameOfLife::create_game_textures(engine),
-            engine: engine,
+            board: GameOfLife::create_board(renderer),
             playground: [false; (PLAYGROUND_WIDTH * PLAYGROUND_HEIGHT) as usize],
             playing: false,
-            cells: BTreeMap::new(),
             last_update: Instant::now(),
             game_speed: Duration::from_millis(500),
         }
     }
 
-    fn update(&mut self) {
-        unsafe {
-            self.handle_input();
-            let now = Instant::now();
-            if now - self.last_update > self.game_speed {
-                self.update_world();
-                self.last_update = now;
-            }
-            self.render_playground();
+    fn update(&mut self, ctx: &mut EngineContext) {
+        self.handle_input(ctx.get_input());
+        let now = Instant::now();
+        if now - self.last_update > self.game_speed {
+            self.update_world();
+            self.last_update = now;
         }
+        self.render_playground(ctx.get_render_mut());
     }
 }
 
-impl<'a> GameOfLife<'a> {
+impl GameOfLife {
     pub fn get(&mut self, x: i32, y: i32) -> Option<&bool> {
         match self.get_mut(x, y) {
             Some(x) => Some(x),
@@ -69,23 +63,24 @@ impl<'a> GameOfLife<'a> {
         }
     }
 
-    fn create_game_textures(engine: *mut BlightCore<'a>) -> Texture<'a> {
-        unsafe {
-            let renderer = (*engine).get_render();
-            let mut texture = (*renderer).create_texture(&(CELL_SIZE, CELL_SIZE));
-            GameOfLife::init_game_textures(&mut texture, renderer);
-            texture
-        }
-    }
-
-    unsafe fn init_game_textures(texture: &mut Texture<'a>, renderer: *mut RenderSystem) {
-        (*renderer)
-            .get_canvas_mut()
-            .with_texture_canvas(texture, |texture| {
-                texture.set_draw_color(Color::RGB(0, 0, 0));
-                texture.clear();
-            })
-            .unwrap();
+    fn create_board(renderer: &mut RenderSystem) -> RenderComponent {
+        let cell = Image {
+            width: CELL_SIZE,
+            height: CELL_SIZE,
+            pixels: [0, 0, 0, 255].repeat((CELL_SIZE * CELL_SIZE) as usize),
+        };
+        let texture = renderer.create_texture_from_image(&cell).unwrap();
+        let mut tileset = Tileset::new(renderer.add_texture(texture));
+        tileset.push(Rect::new(0, 0, CELL_SIZE, CELL_SIZE));
+        let mut tilemap = Tilemap::new(
+            tileset,
+            (PLAYGROUND_WIDTH, PLAYGROUND_HEIGHT),
+            (CELL_SIZE, CELL_SIZE),
+        );
+        tilemap.add_layer("cells");
+        let board = renderer.create_component();
+        renderer.try_get_mut(&board).unwrap().set_tilemap(tilemap);
+        board
     }
 
     fn update_world(&mut self) {
@@ -117,55 +112,55 @@ impl<'a> GameOfLife<'a> {
         self.playground = new_playground;
     }
 
-    unsafe fn handle_input(&mut self) {
-        (*self.engine)
-            .get_input()
-            .handle_events(&mut |event| match event {
-                Event::KeyDown {
-                    keycode: Some(Keycode::Space),
-                    repeat: false,
-                    ..
-                } => {
-                    self.playing = !self.playing;
-                }
-                Event::MouseButtonDown {
-                    x,
-                    y,
-                    mouse_btn: MouseButton::Left,
-                    ..
-                } => {
-                    let x = ((*x as u32) / CELL_SIZE) as i32;
-                    let y = ((*y as u32) / CELL_SIZE) as i32;
-                    if let Some(cell) = self.get_mut(x, y) {
-                        *cell = !*cell;
-                    }
+    fn handle_input(&mut self, input: &InputSystem) {
+        input.handle_events(&mut |event| match event {
+            Event::KeyDown {
+                keycode: Some(Keycode::Space),
+                repeat: false,
+                ..
+            } => {
+                self.playing = !self.playing;
+            }
+            Event::MouseButtonDown {
+                x,
+                y,
+                mouse_btn: MouseButton::Left,
+                ..
+            } => {
+                let x = ((*x as u32) / CELL_SIZE) as i32;
+                let y = ((*y as u32) / CELL_SIZE) as i32;
+                if let Some(cell) = self.get_mut(x, y) {
+                    *cell = !*cell;
                 }
-                _ => {}
-            });
+            }
+            _ => {}
+        });
     }
 
-    unsafe fn render_playground(&mut self) {
-        let renderer = (*self.engine).get_render();
+    fn render_playground(&mut self, renderer: &mut RenderSystem) {
+        let tilemap = renderer
+            .try_get_mut(&self.board)
+            .unwrap()
+            .tilemap
+            .as_mut()
+            .unwrap();
         for (i, cell) in self.playground.iter().enumerate() {
-            if !*cell {
-                self.cells.remove(&i);
-                continue;
-            }
-            if !self.cells.contains_key(&i) {
-                let mut component = (*renderer).create_component();
-                let x = i as u32;
-                component.position = Rect::new(
-                    ((x % PLAYGROUND_WIDTH) * CELL_SIZE) as i32,
-                    ((x / PLAYGROUND_WIDTH) * CELL_SIZE) as i32,
-                    CELL_SIZE,
-                    CELL_SIZE,
-                );
-                self.cells.insert(i, component);
-            }
-            let mut component = self.cells.get_mut(&i).unwrap();
-            component.texture = &self.cell_texture;
+            let (x, y) = (i as u32 % PLAYGROUND_WIDTH, i as u32 / PLAYGROUND_WIDTH);
+            tilemap.set_tile(0, x, y, if *cell { Some(Tile::new(0)) } else { None });
         }
     }
 }
 
-blight_main!(GameOfLife);
+fn config() -> EngineConfig {
+    EngineConfig {
+        window: WindowConfig {
+            title: "Game of Life".to_string(),
+            size: WINDOW_SIZE,
+            vsync: true,
+            ..WindowConfig::default()
+        },
+        ..EngineConfig::default()
+    }
+}
+
+blight_main!(GameOfLife, config());
@@ -0,0 +1,228 @@
+/// Handle to a value in an `Arena`
+/// Slots are reused after removal, the generation tells the old and the new values apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Index {
+    index: usize,
+    generation: u32,
+}
+
+impl Index {
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Generational arena, a `Vec` with stable handles that detect when their value was removed
+#[derive(Debug, Clone)]
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena {
+            slots: vec![],
+            free: vec![],
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, value: T) -> Index {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.value = Some(value);
+                Index {
+                    index: index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                Index {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Remove the value of `index`, returns `None` if it was already removed
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        if !self.contains(index) {
+            return None;
+        }
+        let slot = &mut self.slots[index.index];
+        // Invalidate the handles to the removed value
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index.index);
+        self.len -= 1;
+        slot.value.take()
+    }
+
+    /// Move the value of `index` out, its slot stays reserved until the value is restored
+    /// Meanwhile `get` returns `None`, but the handles to the value remain valid
+    pub fn take(&mut self, index: Index) -> Option<T> {
+        if !self.contains(index) {
+            return None;
+        }
+        self.slots[index.index].value.take()
+    }
+
+    /// Put back a value moved out by `take`
+    pub fn restore(&mut self, index: Index, value: T) {
+        let slot = &mut self.slots[index.index];
+        assert!(
+            slot.generation == index.generation && slot.value.is_none(),
+            "Only taken values can be restored"
+        );
+        slot.value = Some(value);
+    }
+
+    pub fn contains(&self, index: Index) -> bool {
+        self.get(index).is_some()
+    }
+
+    pub fn get(&self, index: Index) -> Option<&T> {
+        match self.slots.get(index.index) {
+            Some(slot) if slot.generation == index.generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        match self.slots.get_mut(index.index) {
+            Some(slot) if slot.generation == index.generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let generation = slot.generation;
+            slot.value.as_ref().map(|value| {
+                (
+                    Index {
+                        index: index,
+                        generation: generation,
+                    },
+                    value,
+                )
+            })
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Index, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let generation = slot.generation;
+                slot.value.as_mut().map(|value| {
+                    (
+                        Index {
+                            index: index,
+                            generation: generation,
+                        },
+                        value,
+                    )
+                })
+            })
+    }
+
+    /// Remove every value, invalidating all handles
+    pub fn clear(&mut self) {
+        let indices: Vec<Index> = self.iter().map(|(index, _)| index).collect();
+        for index in indices {
+            self.remove(index);
+        }
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.get(a), Some(&"a"));
+        assert_eq!(arena.get(b), Some(&"b"));
+    }
+
+    #[test]
+    fn test_removed_handles_are_stale_after_reuse() {
+        let mut arena = Arena::new();
+        let old = arena.insert(1);
+
+        assert_eq!(arena.remove(old), Some(1));
+        assert_eq!(arena.remove(old), None);
+
+        let new = arena.insert(2);
+        assert_eq!(new.get_index(), old.get_index());
+        assert_ne!(new, old);
+        assert!(arena.get(old).is_none());
+        assert_eq!(arena.get(new), Some(&2));
+    }
+
+    #[test]
+    fn test_clear_invalidates_every_handle() {
+        let mut arena = Arena::new();
+        let handles: Vec<Index> = (0..5).map(|i| arena.insert(i)).collect();
+
+        arena.clear();
+
+        assert!(arena.is_empty());
+        assert!(handles.iter().all(|handle| !arena.contains(*handle)));
+        assert_eq!(arena.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_taken_values_keep_their_slot() {
+        let mut arena = Arena::new();
+        let taken = arena.insert(1);
+
+        assert_eq!(arena.take(taken), Some(1));
+        assert!(arena.get(taken).is_none());
+        let other = arena.insert(2);
+        assert_ne!(other.get_index(), taken.get_index());
+
+        arena.restore(taken, 3);
+        assert_eq!(arena.get(taken), Some(&3));
+        assert_eq!(arena.len(), 2);
+    }
+}
@@ -14,6 +14,14 @@ impl Circle {
         }
     }
 
+    pub fn get_center(&self) -> &Vector2 {
+        &self.center
+    }
+
+    pub fn get_radius(&self) -> f32 {
+        self.radius
+    }
+
     pub fn intersects(&self, other: &Circle) -> bool {
         let distance = self.center.sub(&other.center);
         let dist_sqrd = distance.dot(&distance);
@@ -4,3 +4,4 @@ pub mod circle;
 pub mod obb;
 pub mod matrix;
 pub mod quadtree;
+pub mod arena;
@@ -1,6 +1,5 @@
 pub use super::aabb::AABB;
 pub use super::vector2::Vector2;
-use std::mem;
 
 #[derive(Debug)]
 pub enum QuadtreeError {
@@ -21,8 +20,7 @@ where
     T: Spacial,
 {
     boundary: AABB,
-    points: [T; CAPACITY],
-    len: usize,
+    points: Vec<T>,
     children: Option<[Box<Quadtree<T>>; 4]>,
 }
 
@@ -31,13 +29,10 @@ where
     T: Spacial,
 {
     pub fn new(boundary: AABB) -> Self {
-        unsafe {
-            Self {
-                boundary: boundary,
-                points: mem::uninitialized(),
-                len: 0,
-                children: None,
-            }
+        Self {
+            boundary: boundary,
+            points: Vec::with_capacity(CAPACITY),
+            children: None,
         }
     }
 
@@ -48,9 +43,8 @@ where
             return assertion;
         }
 
-        if self.len < CAPACITY {
-            self.points[self.len] = point;
-            self.len += 1;
+        if self.points.len() < CAPACITY {
+            self.points.push(point);
             return Ok(());
         }
 
@@ -136,8 +130,7 @@ where
             return;
         }
 
-        for i in 0..self.len {
-            let p = &self.points[i];
+        for p in &self.points {
             if range.contains(p.position()) {
                 result.push(p);
             }
@@ -152,9 +145,33 @@ where
         });
     }
 
+    pub fn get_boundary(&self) -> &AABB {
+        &self.boundary
+    }
+
+    /// The tree holds no points
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+            && self
+                .children
+                .as_ref()
+                .is_none_or(|children| children.iter().all(|child| child.is_empty()))
+    }
+
+    /// Boundaries of this node and of its descendants holding points
+    pub fn boundaries(&self) -> Vec<&AABB> {
+        let mut result = vec![&self.boundary];
+        if let Some(ref children) = self.children {
+            for child in children.iter().filter(|child| !child.is_empty()) {
+                result.append(&mut child.boundaries());
+            }
+        }
+        result
+    }
+
     /// Clear the tree, retaining already allocated memory for later use
     pub fn clear(&mut self) {
-        self.len = 0;
+        self.points.clear();
         if let Some(ref mut children) = self.children {
             children.iter_mut().for_each(|child| child.clear());
         }
@@ -165,6 +182,7 @@ where
 mod test {
     use super::*;
     use rand::prelude::*;
+    use std::rc::Rc;
     use test::Bencher;
 
     #[derive(Debug, Clone)]
@@ -203,6 +221,63 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_boundaries_skip_empty_nodes() {
+        let boundary = AABB::from_radius(Vector2::new(0., 0.), Vector2::new(20., 20.));
+        let mut tree = Quadtree::<SimpleType>::new(boundary);
+        for i in 0..5 {
+            tree.insert(SimpleType {
+                pos: Vector2::new(10. + i as f32, 10.),
+                value: i,
+            })
+            .unwrap();
+        }
+
+        assert_eq!(tree.boundaries().len(), 2);
+
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(tree.boundaries().len(), 1);
+    }
+
+    #[test]
+    fn test_points_are_dropped_once() {
+        #[derive(Debug)]
+        struct Shared {
+            pos: Vector2,
+            _counter: Rc<()>,
+        }
+
+        impl Spacial for Shared {
+            fn position(&self) -> &Vector2 {
+                &self.pos
+            }
+        }
+
+        let counter = Rc::new(());
+        let boundary = AABB::from_radius(Vector2::new(0., 0.), Vector2::new(20., 20.));
+        let mut tree = Quadtree::new(boundary);
+        for i in 0..10 {
+            tree.insert(Shared {
+                pos: Vector2::new(i as f32, 1.),
+                _counter: counter.clone(),
+            })
+            .unwrap();
+        }
+        assert_eq!(Rc::strong_count(&counter), 11);
+
+        tree.clear();
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        tree.insert(Shared {
+            pos: Vector2::new(1., 1.),
+            _counter: counter.clone(),
+        })
+        .unwrap();
+        drop(tree);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
     #[test]
     fn test_can_insert_many() {
         let boundary = AABB::from_radius(Vector2::new(0., 0.), Vector2::new(25., 20.));
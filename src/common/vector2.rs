@@ -24,6 +24,12 @@ impl Vector2 {
         Vector2::new(-self.y, self.x)
     }
 
+    /// Rotate `self` clockwise on the screen by `degrees` returning a new vector
+    pub fn rotate(&self, degrees: f32) -> Vector2 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Vector2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
     pub fn length(&self) -> f32 {
         self.dot(self).sqrt()
     }
@@ -166,4 +172,11 @@ mod test {
 
         assert_eq!(result, 5.);
     }
+    #[test]
+    fn test_rotate() {
+        let result = Vector2::new(1., 0.).rotate(90.);
+
+        assert!(result.x.abs() < 1e-6);
+        assert!((result.y - 1.).abs() < 1e-6);
+    }
 }
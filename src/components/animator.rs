@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::super::systems::render::render_component::RenderComponentInner;
+use super::super::systems::render::{Rect, SpriteSheet, TagDirection, TextureAtlas, TextureHandle};
+
+/// What happens when an `AnimationClip` reaches its last frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationMode {
+    /// Start over from the first frame
+    Loop,
+    /// Play the frames backwards to the first frame, then forwards again
+    PingPong,
+    /// Stop on the last frame
+    Once,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame {
+    pub texture: TextureHandle,
+    pub source: Rect,
+    pub duration: Duration,
+}
+
+/// Frames played after each other by an `Animator`
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    frames: Vec<AnimationFrame>,
+    mode: AnimationMode,
+}
+
+impl AnimationClip {
+    pub fn new(mode: AnimationMode) -> AnimationClip {
+        AnimationClip {
+            frames: vec![],
+            mode: mode,
+        }
+    }
+
+    /// Clip of the frames of `sheet` at `indices`, each shown for `duration`
+    /// Returns `None` if the sheet has no frame at one of the indices, or `duration` is zero
+    pub fn from_sprite_sheet(
+        sheet: &SpriteSheet,
+        indices: &[usize],
+        duration: Duration,
+        mode: AnimationMode,
+    ) -> Option<AnimationClip> {
+        if duration == Duration::from_secs(0) {
+            return None;
+        }
+        let mut clip = AnimationClip::new(mode);
+        for index in indices {
+            clip.push(sheet.get_texture(), sheet.frame(*index)?, duration);
+        }
+        Some(clip)
+    }
+
+    /// Clip of the frames of the tag named `tag` of `atlas`
+    /// Frames without a duration in the atlas are shown for `default_duration`
+    /// Returns `None` if there is no such tag, or a frame would be shown for no time
+    /// The tag's direction decides the order of the frames and whether they ping-pong, the
+    /// clip loops either way
+    pub fn from_atlas_tag(
+        atlas: &TextureAtlas,
+        tag: &str,
+        default_duration: Duration,
+    ) -> Option<AnimationClip> {
+        let tag = atlas.get_tag(tag)?;
+        let mode = match tag.direction {
+            TagDirection::PingPong | TagDirection::PingPongReverse => AnimationMode::PingPong,
+            TagDirection::Forward | TagDirection::Reverse => AnimationMode::Loop,
+        };
+        let mut clip = AnimationClip::new(mode);
+        for index in tag.from..=tag.to {
+            let frame = atlas.get_frame(index)?;
+            let duration = frame.duration.unwrap_or(default_duration);
+            if duration == Duration::from_secs(0) {
+                return None;
+            }
+            clip.push(atlas.get_texture(), frame.source, duration);
+        }
+        if tag.direction == TagDirection::Reverse || tag.direction == TagDirection::PingPongReverse
+        {
+            clip.frames.reverse();
+        }
+        Some(clip)
+    }
+
+    /// Append a frame showing `source` of `texture` for `duration`
+    pub fn push(&mut self, texture: TextureHandle, source: Rect, duration: Duration) {
+        assert!(
+            duration > Duration::from_secs(0),
+            "Frames can not be instant"
+        );
+        self.frames.push(AnimationFrame {
+            texture: texture,
+            source: source,
+            duration: duration,
+        });
+    }
+
+    pub fn get_frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    pub fn get_mode(&self) -> AnimationMode {
+        self.mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// Things that happened to an `Animator` during the last fixed update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// The clip moved to the frame at this index
+    FrameChanged { clip: String, frame: usize },
+    /// A looping or ping-ponging clip got back to its first frame
+    Looped { clip: String },
+    /// A clip played `Once` reached its end
+    Finished { clip: String },
+}
+
+/// Cycles the `RenderComponent` of its entity through the frames of named clips
+/// The engine advances it every fixed update, after the systems ran
+///```
+/// use blight::components::animator::{AnimationClip, AnimationMode, Animator};
+///
+/// let mut animator = Animator::new();
+/// animator.add_clip("idle", AnimationClip::new(AnimationMode::Loop));
+/// assert!(animator.play("idle"));
+/// assert!(!animator.play("run"));
+/// assert_eq!(animator.get_clip_name(), Some("idle"));
+///```
+#[derive(Debug, Clone)]
+pub struct Animator {
+    clips: HashMap<String, AnimationClip>,
+    current: Option<String>,
+    frame: usize,
+    /// Time spent on the current frame in seconds
+    elapsed: f32,
+    /// Direction of a ping-ponging clip
+    forward: bool,
+    playing: bool,
+    finished: bool,
+    events: Vec<AnimationEvent>,
+    /// Playback rate, 2 plays twice as fast and 0 freezes the animation
+    pub speed: f32,
+}
+
+impl Animator {
+    pub fn new() -> Animator {
+        Animator {
+            clips: HashMap::new(),
+            current: None,
+            frame: 0,
+            elapsed: 0.,
+            forward: true,
+            playing: false,
+            finished: false,
+            events: vec![],
+            speed: 1.,
+        }
+    }
+
+    /// Add a clip named `name`, replacing the previous clip of the same name
+    pub fn add_clip(&mut self, name: &str, clip: AnimationClip) {
+        self.clips.insert(name.to_string(), clip);
+    }
+
+    pub fn get_clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    /// Play the clip named `name` from its first frame
+    /// Does nothing if the clip is already playing, so it can be called every update
+    /// Returns `false` if there is no such clip
+    pub fn play(&mut self, name: &str) -> bool {
+        if !self.clips.contains_key(name) {
+            return false;
+        }
+        if !self.playing || self.get_clip_name() != Some(name) {
+            self.current = Some(name.to_string());
+            self.restart();
+        }
+        true
+    }
+
+    /// Play the current clip from its first frame
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = 0.;
+        self.forward = true;
+        self.playing = self.current.is_some();
+        self.finished = false;
+    }
+
+    /// Stop on the current frame
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Continue playing from the current frame, unless the clip finished
+    pub fn resume(&mut self) {
+        self.playing = self.current.is_some() && !self.finished;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// The current clip was played `Once` and reached its last frame
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Name of the clip played or paused
+    pub fn get_clip_name(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Index of the shown frame of the current clip
+    pub fn get_frame_index(&self) -> usize {
+        self.frame
+    }
+
+    /// The shown frame, `None` if no clip is playing or it is empty
+    pub fn get_frame(&self) -> Option<&AnimationFrame> {
+        self.current_clip()
+            .and_then(|clip| clip.frames.get(self.frame))
+    }
+
+    /// What happened during the last fixed update
+    pub fn get_events(&self) -> &[AnimationEvent] {
+        &self.events
+    }
+
+    /// Advance the animation by `delta_time`, scaled by `speed`
+    /// Clears the events of the previous update
+    pub fn update(&mut self, delta_time: &Duration) {
+        self.events.clear();
+        if !self.playing {
+            return;
+        }
+        let (name, clip) = match self.current {
+            Some(ref name) => match self.clips.get(name) {
+                Some(clip) if !clip.is_empty() => (name, clip),
+                _ => return,
+            },
+            None => return,
+        };
+        self.elapsed += delta_time.as_secs_f32() * self.speed.max(0.);
+        loop {
+            let duration = clip.frames[self.frame].duration.as_secs_f32();
+            if self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+            let previous = self.frame;
+            let last = clip.len() - 1;
+            let looped = match clip.mode {
+                AnimationMode::Once if self.frame >= last => {
+                    self.playing = false;
+                    self.finished = true;
+                    self.elapsed = 0.;
+                    self.events
+                        .push(AnimationEvent::Finished { clip: name.clone() });
+                    break;
+                }
+                AnimationMode::Once => {
+                    self.frame += 1;
+                    false
+                }
+                AnimationMode::Loop if self.frame >= last => {
+                    self.frame = 0;
+                    true
+                }
+                AnimationMode::Loop => {
+                    self.frame += 1;
+                    false
+                }
+                AnimationMode::PingPong => {
+                    if self.forward && self.frame >= last || !self.forward && self.frame == 0 {
+                        self.forward = !self.forward;
+                    }
+                    if self.forward {
+                        self.frame = (self.frame + 1).min(last);
+                    } else {
+                        self.frame = self.frame.saturating_sub(1);
+                    }
+                    // A cycle ends when the first frame is reached again
+                    !self.forward && self.frame == 0
+                }
+            };
+            if looped {
+                self.events
+                    .push(AnimationEvent::Looped { clip: name.clone() });
+            }
+            if self.frame != previous {
+                self.events.push(AnimationEvent::FrameChanged {
+                    clip: name.clone(),
+                    frame: self.frame,
+                });
+            }
+        }
+    }
+
+    /// Show the current frame with `component`
+    pub fn apply(&self, component: &mut RenderComponentInner) {
+        if let Some(frame) = self.get_frame() {
+            component.texture = Some(frame.texture);
+            component.source = Some(frame.source);
+        }
+    }
+
+    fn current_clip(&self) -> Option<&AnimationClip> {
+        self.current.as_ref().and_then(|name| self.clips.get(name))
+    }
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Animator::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::common::arena::Arena;
+    use super::super::super::core::EngineContext;
+    use super::super::super::ecs::Entity;
+    use super::super::super::systems::render::RenderComponent;
+    use super::super::super::testing::GameHarness;
+    use super::super::super::Game;
+    use super::*;
+
+    fn clip(frames: usize, mode: AnimationMode) -> AnimationClip {
+        let texture = TextureHandle(Arena::new().insert(()));
+        let mut clip = AnimationClip::new(mode);
+        for i in 0..frames {
+            clip.push(
+                texture,
+                Rect::new(i as i32 * 8, 0, 8, 8),
+                Duration::from_millis(100),
+            );
+        }
+        clip
+    }
+
+    fn frames_played(animator: &mut Animator, updates: usize) -> Vec<usize> {
+        (0..updates)
+            .map(|_| {
+                animator.update(&Duration::from_millis(100));
+                animator.get_frame_index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_loop_and_ping_pong() {
+        let mut animator = Animator::new();
+        animator.add_clip("loop", clip(3, AnimationMode::Loop));
+        animator.add_clip("pingpong", clip(3, AnimationMode::PingPong));
+
+        animator.play("loop");
+        assert_eq!(frames_played(&mut animator, 4), vec![1, 2, 0, 1]);
+
+        animator.play("pingpong");
+        assert_eq!(frames_played(&mut animator, 6), vec![1, 2, 1, 0, 1, 2]);
+        assert_eq!(
+            animator.get_events(),
+            &[AnimationEvent::FrameChanged {
+                clip: "pingpong".to_string(),
+                frame: 2
+            }]
+        );
+
+        frames_played(&mut animator, 2);
+        assert_eq!(
+            animator.get_events()[0],
+            AnimationEvent::Looped {
+                clip: "pingpong".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_frames_of_no_duration() {
+        let texture = TextureHandle(Arena::new().insert(()));
+        let json = r#"{
+            "frames": [
+                {"filename": "a", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 0},
+                {"filename": "b", "frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 50}
+            ],
+            "meta": {"frameTags": [{"name": "idle", "from": 0, "to": 1}]}
+        }"#;
+        let atlas = TextureAtlas::from_json(texture, json).unwrap();
+
+        let clip = AnimationClip::from_atlas_tag(&atlas, "idle", Duration::from_millis(100));
+        let durations: Vec<Duration> = clip
+            .unwrap()
+            .get_frames()
+            .iter()
+            .map(|frame| frame.duration)
+            .collect();
+        assert_eq!(
+            durations,
+            vec![Duration::from_millis(100), Duration::from_millis(50)]
+        );
+        assert!(AnimationClip::from_atlas_tag(&atlas, "idle", Duration::from_secs(0)).is_none());
+
+        let sheet = SpriteSheet::new(texture, (16, 8), (8, 8));
+        let clip = AnimationClip::from_sprite_sheet(
+            &sheet,
+            &[0, 1],
+            Duration::from_secs(0),
+            AnimationMode::Loop,
+        );
+        assert!(clip.is_none());
+    }
+
+    #[test]
+    fn test_once_finishes_on_the_last_frame() {
+        let mut animator = Animator::new();
+        animator.add_clip("attack", clip(2, AnimationMode::Once));
+        animator.play("attack");
+
+        animator.update(&Duration::from_millis(250));
+
+        assert_eq!(animator.get_frame_index(), 1);
+        assert!(animator.is_finished());
+        assert!(!animator.is_playing());
+        assert_eq!(
+            animator.get_events(),
+            &[
+                AnimationEvent::FrameChanged {
+                    clip: "attack".to_string(),
+                    frame: 1
+                },
+                AnimationEvent::Finished {
+                    clip: "attack".to_string()
+                },
+            ]
+        );
+
+        animator.update(&Duration::from_millis(100));
+        assert!(animator.get_events().is_empty());
+        assert!(animator.play("attack"));
+        assert_eq!(animator.get_frame_index(), 0);
+    }
+
+    #[test]
+    fn test_speed_scales_the_frame_durations() {
+        let mut animator = Animator::new();
+        animator.add_clip("run", clip(4, AnimationMode::Loop));
+        animator.play("run");
+        animator.speed = 2.;
+
+        animator.update(&Duration::from_millis(100));
+        assert_eq!(animator.get_frame_index(), 2);
+
+        // Playing the current clip again does not restart it
+        animator.play("run");
+        animator.speed = 0.;
+        animator.update(&Duration::from_millis(100));
+        assert_eq!(animator.get_frame_index(), 2);
+    }
+
+    struct Animated {
+        entity: Entity,
+    }
+
+    impl Game for Animated {
+        fn init(ctx: &mut EngineContext) -> Self {
+            let component = ctx.get_render_mut().create_component();
+            let mut animator = Animator::new();
+            animator.add_clip("idle", clip(2, AnimationMode::Loop));
+            animator.play("idle");
+            let world = ctx.get_world_mut();
+            let entity = world.create_entity();
+            world.add_component(entity, component);
+            world.add_component(entity, animator);
+            Animated { entity: entity }
+        }
+    }
+
+    #[test]
+    fn test_engine_animates_the_render_component() {
+        let mut harness = GameHarness::<Animated>::new();
+
+        // Six fixed updates of a sixtieth of a second pass the first frame
+        harness.step(7);
+
+        let ctx = harness.engine().get_context();
+        let entity = harness.game().entity;
+        let component = ctx
+            .get_world()
+            .get_component::<RenderComponent>(entity)
+            .unwrap();
+        let component = ctx.get_render().try_get(component).unwrap();
+        assert_eq!(component.source, Some(Rect::new(8, 0, 8, 8)));
+    }
+}
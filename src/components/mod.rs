@@ -1 +1,3 @@
+pub mod animator;
+pub mod position;
 pub mod render;
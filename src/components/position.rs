@@ -0,0 +1,12 @@
+use super::super::common::vector2::Vector2;
+
+/// Center of an entity in the world
+/// The engine moves the entity's `RenderComponent` and collider along with it
+#[derive(Debug, Clone)]
+pub struct Position(pub Vector2);
+
+impl Position {
+    pub fn new(x: f32, y: f32) -> Position {
+        Position(Vector2::new(x, y))
+    }
+}
@@ -1,40 +1,50 @@
-use super::super::systems::render::{RenderComponentInner, RenderSystem};
-use std::ops::{Deref, DerefMut};
+use super::super::systems::render::ComponentHandle;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Weak;
 
-/// Proxy object to RenderComponents
-#[derive(Debug, Clone)]
-pub struct RenderComponent<'a> {
-    id: usize,
-    system: *mut RenderSystem<'a>,
+/// Owner of a component of the `RenderSystem`, the component is deleted when this is dropped
+/// Access the component through `RenderSystem::try_get` and `RenderSystem::try_get_mut`
+#[derive(Debug)]
+pub struct RenderComponent {
+    handle: ComponentHandle,
+    deleted: Weak<RefCell<HashSet<ComponentHandle>>>,
 }
 
-impl<'a> RenderComponent<'a> {
-    pub fn new(id: usize, system: *mut RenderSystem<'a>) -> Self {
+impl RenderComponent {
+    pub(crate) fn new(
+        handle: ComponentHandle,
+        deleted: Weak<RefCell<HashSet<ComponentHandle>>>,
+    ) -> Self {
         RenderComponent {
-            id: id,
-            system: system,
+            handle: handle,
+            deleted: deleted,
         }
     }
-}
 
-impl<'a> DerefMut for RenderComponent<'a> {
-    fn deref_mut(&mut self) -> &mut RenderComponentInner<'a> {
-        unsafe { (*self.system).get_component_by_id(self.id) }
+    pub fn handle(&self) -> ComponentHandle {
+        self.handle
     }
-}
 
-impl<'a> Deref for RenderComponent<'a> {
-    type Target = RenderComponentInner<'a>;
+    /// Give up the ownership of the component
+    /// It lives until deleted via its handle, or until its scope is purged
+    pub fn release(mut self) -> ComponentHandle {
+        self.deleted = Weak::new();
+        self.handle
+    }
+}
 
-    fn deref(&self) -> &RenderComponentInner<'a> {
-        unsafe { (*self.system).get_component_by_id(self.id) }
+impl<'a> From<&'a RenderComponent> for ComponentHandle {
+    fn from(component: &'a RenderComponent) -> ComponentHandle {
+        component.handle
     }
 }
 
-impl<'a> Drop for RenderComponent<'a> {
+impl Drop for RenderComponent {
     fn drop(&mut self) {
-        unsafe {
-            (*self.system).delete_components_by_ids(&[self.id]);
+        // The render system deletes the component the next time it is used
+        if let Some(deleted) = self.deleted.upgrade() {
+            deleted.borrow_mut().insert(self.handle);
         }
     }
 }
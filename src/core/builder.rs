@@ -0,0 +1,170 @@
+use sdl2;
+use std::path::PathBuf;
+
+use super::super::systems::input::{EventQueue, EventSource, InputSystem, Keycode};
+use super::super::systems::render::RenderSystem;
+use super::{BlightCore, ConfigError, EngineConfig};
+
+/// Configures and creates a `BlightCore`
+///```no_run
+/// use blight::core::BlightCoreBuilder;
+/// use blight::systems::input::EventQueue;
+///
+/// let events = EventQueue::new();
+/// let engine = BlightCoreBuilder::new()
+///     .headless()
+///     .event_source(events.clone())
+///     .build()
+///     .unwrap();
+///
+/// let engine = BlightCoreBuilder::new()
+///     .title("Game of Life")
+///     .window_size(1024, 768)
+///     .vsync()
+///     .updates_per_second(30)
+///     .build()
+///     .unwrap();
+///```
+pub struct BlightCoreBuilder {
+    config: EngineConfig,
+    event_source: Option<Box<dyn EventSource>>,
+}
+
+impl BlightCoreBuilder {
+    pub fn new() -> BlightCoreBuilder {
+        BlightCoreBuilder::from_config(EngineConfig::default())
+    }
+
+    pub fn from_config(config: EngineConfig) -> BlightCoreBuilder {
+        BlightCoreBuilder {
+            config: config,
+            event_source: None,
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.config.window.title = title.to_string();
+        self
+    }
+
+    pub fn window_size(mut self, width: u32, height: u32) -> Self {
+        self.config.window.size = (width, height);
+        self
+    }
+
+    pub fn resizable(mut self) -> Self {
+        self.config.window.resizable = true;
+        self
+    }
+
+    pub fn fullscreen(mut self) -> Self {
+        self.config.window.fullscreen = true;
+        self
+    }
+
+    pub fn vsync(mut self) -> Self {
+        self.config.window.vsync = true;
+        self
+    }
+
+    /// Set the number of fixed updates per second
+    pub fn updates_per_second(mut self, updates: u32) -> Self {
+        self.config.updates_per_second = updates;
+        self
+    }
+
+    /// Set the maximum number of fixed updates ran in a single frame
+    pub fn max_updates_per_frame(mut self, updates: u32) -> Self {
+        self.config.max_updates_per_frame = updates;
+        self
+    }
+
+    /// Set the key requesting the engine to quit, `None` disables it
+    /// Defaults to `Keycode::Escape`
+    pub fn quit_key(mut self, key: Option<Keycode>) -> Self {
+        self.config.quit_key = key;
+        self
+    }
+
+    /// Bind a debug key toggling the pause of the simulation
+    pub fn pause_key(mut self, key: Keycode) -> Self {
+        self.config.pause_key = Some(key);
+        self
+    }
+
+    /// Bind a debug key running a single fixed update while the simulation is paused
+    pub fn step_key(mut self, key: Keycode) -> Self {
+        self.config.step_key = Some(key);
+        self
+    }
+
+    /// Bind a debug key toggling the overlay of the colliders
+    pub fn collision_overlay_key(mut self, key: Keycode) -> Self {
+        self.config.collision_overlay_key = Some(key);
+        self
+    }
+
+    /// Bind a key saving the next frame as a PNG into `directory`
+    pub fn screenshot_key<P>(mut self, key: Keycode, directory: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.config.screenshot_key = Some(key);
+        self.config.screenshot_directory = directory.into();
+        self
+    }
+
+    /// Run without a window, rendering into an offscreen software target
+    /// Unless an event source is given the engine will not receive any events
+    pub fn headless(mut self) -> Self {
+        self.config.headless = true;
+        self
+    }
+
+    /// Read input events from `source` instead of the OS event pump
+    pub fn event_source<T>(mut self, source: T) -> Self
+    where
+        T: EventSource + 'static,
+    {
+        self.event_source = Some(Box::new(source));
+        self
+    }
+
+    /// Create the engine, failing before any window is opened if the config is invalid
+    pub fn build(self) -> Result<BlightCore, ConfigError> {
+        self.config.validate()?;
+        let update_duration = self.config.update_duration()?;
+        if self.config.headless {
+            // Headless engines do not use SDL, so any number of them can be alive at once
+            let source = self
+                .event_source
+                .unwrap_or_else(|| Box::new(EventQueue::new()));
+            let render_system = RenderSystem::headless(&self.config.window);
+            let input_system = InputSystem::from_source(source);
+            return Ok(BlightCore::from_systems(
+                self.config,
+                update_duration,
+                render_system,
+                input_system,
+            ));
+        }
+        let sdl = sdl2::init().unwrap();
+        let render_system = RenderSystem::from_config(&sdl, &self.config.window);
+        let input_system = match self.event_source {
+            Some(source) => InputSystem::from_source(source),
+            None => InputSystem::new(&sdl),
+        };
+        Ok(BlightCore::from_systems(
+            self.config,
+            update_duration,
+            render_system,
+            input_system,
+        ))
+    }
+}
+
+impl Default for BlightCoreBuilder {
+    fn default() -> Self {
+        BlightCoreBuilder::new()
+    }
+}
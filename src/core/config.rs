@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::super::systems::input::Keycode;
+use super::super::systems::render::WindowConfig;
+
+const NANOS_PER_SECOND: u32 = 1_000_000_000;
+
+/// Settings of a `BlightCore`
+///```
+/// use blight::core::EngineConfig;
+///
+/// let mut config = EngineConfig::default();
+/// config.window.title = "My Game".to_string();
+/// config.updates_per_second = 30;
+///
+/// assert_eq!(config.update_duration().unwrap().subsec_nanos(), 33_333_333);
+///
+/// config.updates_per_second = 0;
+/// assert!(config.update_duration().is_err());
+///```
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub window: WindowConfig,
+    /// Render offscreen instead of opening a window
+    pub headless: bool,
+    /// Number of fixed updates per second
+    pub updates_per_second: u32,
+    /// Maximum number of fixed updates ran in a single frame to catch up with the clock
+    /// Time the engine falls behind by more than this is dropped
+    pub max_updates_per_frame: u32,
+    /// Key requesting the engine to quit, see `Game::on_quit_request`
+    pub quit_key: Option<Keycode>,
+    /// Debug key toggling the pause of the simulation
+    pub pause_key: Option<Keycode>,
+    /// Debug key running a single fixed update while the simulation is paused
+    pub step_key: Option<Keycode>,
+    /// Debug key toggling the overlay of the colliders, see `CollisionSystem::get_overlay`
+    pub collision_overlay_key: Option<Keycode>,
+    /// Key saving the next frame as a PNG into `screenshot_directory`
+    pub screenshot_key: Option<Keycode>,
+    pub screenshot_directory: PathBuf,
+}
+
+/// A setting of an `EngineConfig` the engine can not run with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The number of updates per second is 0 or shorter than a nanosecond
+    UpdateRate(u32),
+    /// The maximum number of updates per frame is 0, the simulation would never advance
+    NoUpdatesPerFrame,
+}
+
+impl EngineConfig {
+    /// Duration of a single fixed update
+    pub fn update_duration(&self) -> Result<Duration, ConfigError> {
+        match self.updates_per_second {
+            0 => Err(ConfigError::UpdateRate(0)),
+            updates if updates > NANOS_PER_SECOND => Err(ConfigError::UpdateRate(updates)),
+            updates => Ok(Duration::new(0, NANOS_PER_SECOND / updates)),
+        }
+    }
+
+    /// Check that the engine can run with the settings
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_updates_per_frame == 0 {
+            return Err(ConfigError::NoUpdatesPerFrame);
+        }
+        self.update_duration().map(|_| ())
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            window: WindowConfig::default(),
+            headless: false,
+            updates_per_second: 60,
+            max_updates_per_frame: 10,
+            quit_key: Some(Keycode::Escape),
+            pause_key: None,
+            step_key: None,
+            collision_overlay_key: None,
+            screenshot_key: None,
+            screenshot_directory: PathBuf::from("screenshots"),
+        }
+    }
+}
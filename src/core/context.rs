@@ -0,0 +1,338 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::rect::Point;
+
+use super::super::common::aabb::AABB;
+use super::super::common::vector2::Vector2;
+use super::super::components::animator::Animator;
+use super::super::components::position::Position;
+use super::super::ecs::{Schedule, System, World};
+use super::super::systems::collision::CollisionSystem;
+use super::super::systems::input::InputSystem;
+use super::super::systems::render::{RenderComponent, RenderSystem};
+use super::scene::{Scene, SceneTransition};
+use super::stats::FrameStats;
+use super::EngineConfig;
+
+/// The engine's services available to games
+/// Handed to each `Game` callback by the `BlightCore`
+pub struct EngineContext {
+    world: World,
+    schedule: Schedule,
+    collision_system: CollisionSystem,
+    render_system: Box<RenderSystem>,
+    input_system: Box<InputSystem>,
+    running: bool,
+    delta_time: Duration,
+    frame_stats: FrameStats,
+    paused: bool,
+    time_scale: f32,
+    queued_steps: u32,
+    quit_requested: bool,
+    quit_key: Option<Keycode>,
+    pause_key: Option<Keycode>,
+    step_key: Option<Keycode>,
+    collision_overlay_key: Option<Keycode>,
+    screenshot_key: Option<Keycode>,
+    screenshot_directory: PathBuf,
+    scene_transitions: Vec<SceneTransition>,
+}
+
+impl EngineContext {
+    pub(crate) fn new(
+        config: &EngineConfig,
+        render_system: RenderSystem,
+        input_system: InputSystem,
+    ) -> EngineContext {
+        let (width, height) = config.window.size;
+        let screen = AABB::new(
+            Vector2::new(width as f32 / 2., height as f32 / 2.),
+            width as f32,
+            height as f32,
+        );
+        EngineContext {
+            world: World::new(),
+            schedule: Schedule::new(),
+            collision_system: CollisionSystem::new(screen),
+            render_system: Box::new(render_system),
+            input_system: Box::new(input_system),
+            running: false,
+            delta_time: Duration::from_secs(0),
+            frame_stats: FrameStats::new(),
+            paused: false,
+            time_scale: 1.0,
+            queued_steps: 0,
+            quit_requested: false,
+            quit_key: config.quit_key,
+            pause_key: config.pause_key,
+            step_key: config.step_key,
+            collision_overlay_key: config.collision_overlay_key,
+            screenshot_key: config.screenshot_key,
+            screenshot_directory: config.screenshot_directory.clone(),
+            scene_transitions: vec![],
+        }
+    }
+
+    /// Time simulated by the current fixed update
+    pub fn get_delta_time(&self) -> &Duration {
+        &self.delta_time
+    }
+
+    pub(crate) fn set_delta_time(&mut self, delta_time: Duration) {
+        self.delta_time = delta_time;
+    }
+
+    /// Timing statistics of the recent frames
+    pub fn get_frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    pub(crate) fn get_frame_stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.frame_stats
+    }
+
+    pub fn get_world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn get_world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Run `system` on every fixed update, after the game and the scenes were updated
+    pub fn add_system<S>(&mut self, system: S)
+    where
+        S: System + 'static,
+    {
+        self.schedule.add_system(system);
+    }
+
+    /// Number of systems scheduled
+    pub fn get_system_count(&self) -> usize {
+        self.schedule.len()
+    }
+
+    /// Tracks the entities having both a `Position` and a `Collider`
+    /// Its boundary is the screen unless changed
+    pub fn get_collision(&self) -> &CollisionSystem {
+        &self.collision_system
+    }
+
+    pub fn get_collision_mut(&mut self) -> &mut CollisionSystem {
+        &mut self.collision_system
+    }
+
+    pub fn get_input(&self) -> &InputSystem {
+        &self.input_system
+    }
+
+    pub fn get_input_mut(&mut self) -> &mut InputSystem {
+        &mut self.input_system
+    }
+
+    pub fn get_render(&self) -> &RenderSystem {
+        &self.render_system
+    }
+
+    pub fn get_render_mut(&mut self) -> &mut RenderSystem {
+        &mut self.render_system
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Stop the engine after the current update
+    /// Unlike `request_quit` the game can not veto this
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    /// Ask the engine to quit, the game is consulted via `Game::on_quit_request` after the
+    /// input is handled
+    pub fn request_quit(&mut self) {
+        self.quit_requested = true;
+    }
+
+    pub fn is_quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    pub(crate) fn take_quit_request(&mut self) -> bool {
+        std::mem::replace(&mut self.quit_requested, false)
+    }
+
+    /// While paused no fixed updates are ran, but input is still processed and the game is
+    /// still rendered every frame. The events of paused frames are visible in `Game::render`
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.queued_steps = 0;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        let paused = !self.paused;
+        self.set_paused(paused);
+    }
+
+    /// Run a single fixed update on the next frame while paused
+    pub fn step_once(&mut self) {
+        if self.paused {
+            self.queued_steps += 1;
+        }
+    }
+
+    pub(crate) fn take_queued_steps(&mut self) -> u32 {
+        std::mem::replace(&mut self.queued_steps, 0)
+    }
+
+    /// Speed of the fixed update clock relative to real time
+    pub fn get_time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Slow down (`scale < 1`) or speed up (`scale > 1`) the simulation
+    /// Each fixed update still simulates the same amount of time, they are just ran less or
+    /// more often
+    /// Panics if `scale` is negative, infinite or NaN
+    pub fn set_time_scale(&mut self, scale: f32) {
+        assert!(
+            scale.is_finite() && scale >= 0.,
+            "Time scale has to be finite and not negative"
+        );
+        self.time_scale = scale;
+    }
+
+    /// Push `scene` on top of the scene stack
+    /// Like every scene transition it takes effect after the current update or render
+    pub fn push_scene<S>(&mut self, scene: S)
+    where
+        S: Scene + 'static,
+    {
+        self.scene_transitions
+            .push(SceneTransition::Push(Box::new(scene)));
+    }
+
+    /// Pop the top scene, resuming the one below it
+    pub fn pop_scene(&mut self) {
+        self.scene_transitions.push(SceneTransition::Pop);
+    }
+
+    /// Replace the top scene with `scene`, the scene below is not resumed in between
+    pub fn replace_scene<S>(&mut self, scene: S)
+    where
+        S: Scene + 'static,
+    {
+        self.scene_transitions
+            .push(SceneTransition::Replace(Box::new(scene)));
+    }
+
+    pub(crate) fn take_scene_transitions(&mut self) -> Vec<SceneTransition> {
+        std::mem::take(&mut self.scene_transitions)
+    }
+
+    /// Run the scheduled systems, then the engine's own ones
+    pub(crate) fn run_systems(&mut self) {
+        // Take ownership of the schedule so the systems can access the context
+        let mut schedule = std::mem::take(&mut self.schedule);
+        schedule.run(self);
+        // Keep the systems added while running
+        schedule.append(&mut self.schedule);
+        self.schedule = schedule;
+
+        let render_system = &mut self.render_system;
+        let delta_time = &self.delta_time;
+        self.world
+            .for_each2::<Animator, RenderComponent, _>(|_, animator, component| {
+                animator.update(delta_time);
+                if let Some(component) = render_system.try_get_mut(&*component) {
+                    animator.apply(component);
+                }
+            });
+        render_system.update_tilemaps(delta_time);
+        self.world
+            .for_each2::<Position, RenderComponent, _>(|_, position, component| {
+                if let Some(component) = render_system.try_get_mut(&*component) {
+                    let center =
+                        Point::new(position.0.x.round() as i32, position.0.y.round() as i32);
+                    component.position.center_on(center);
+                }
+            });
+        self.collision_system.update(&self.world);
+    }
+
+    /// Queue the enabled debug overlays for the next frame
+    pub(crate) fn draw_overlays(&mut self) {
+        if self.collision_system.get_overlay().enabled {
+            self.collision_system
+                .draw_overlay(self.render_system.get_primitives_mut());
+        }
+    }
+
+    pub(crate) fn start(&mut self) {
+        self.running = true;
+    }
+
+    pub(crate) fn update_input(&mut self) {
+        self.input_system.update();
+        let (quit_key, pause_key, step_key) = (self.quit_key, self.pause_key, self.step_key);
+        let (overlay_key, screenshot_key) = (self.collision_overlay_key, self.screenshot_key);
+        let mut quit = false;
+        let mut toggle_pause = false;
+        let mut toggle_overlay = false;
+        let mut screenshot = false;
+        let mut steps = 0;
+        self.input_system.handle_events(&mut |event| match event {
+            Event::Quit { .. } => quit = true,
+            Event::KeyDown {
+                keycode: Some(key),
+                repeat,
+                ..
+            } => {
+                let key = Some(*key);
+                if key == quit_key {
+                    quit = true;
+                } else if !*repeat && key == pause_key {
+                    toggle_pause = !toggle_pause;
+                } else if !*repeat && key == step_key {
+                    steps += 1;
+                } else if !*repeat && key == overlay_key {
+                    toggle_overlay = !toggle_overlay;
+                } else if !*repeat && key == screenshot_key {
+                    screenshot = true;
+                }
+            }
+            _ => {}
+        });
+        if quit {
+            self.request_quit();
+        }
+        if toggle_pause {
+            self.toggle_pause();
+        }
+        for _ in 0..steps {
+            self.step_once();
+        }
+        if toggle_overlay {
+            let overlay = self.collision_system.get_overlay_mut();
+            overlay.enabled = !overlay.enabled;
+        }
+        if screenshot {
+            // Name the file after the time it was taken, in milliseconds since the epoch
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            let millis = time.as_secs() * 1000 + u64::from(time.subsec_millis());
+            let path = self
+                .screenshot_directory
+                .join(format!("screenshot-{}.png", millis));
+            self.render_system.save_screenshot(path);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+mod builder;
+mod config;
+mod context;
+mod scene;
+mod stats;
+
+use std::time::{Duration, Instant};
+
+use super::systems::input::InputSystem;
+use super::systems::render::RenderSystem;
+use super::Game;
+
+pub use self::builder::BlightCoreBuilder;
+pub use self::config::{ConfigError, EngineConfig};
+pub use self::context::EngineContext;
+pub use self::scene::{Scene, SceneStack};
+pub use self::stats::{FrameStats, FrameTiming, FRAME_HISTORY};
+
+/// The hearth of the Blight Engine
+/// It's responsible for the game loop
+pub struct BlightCore {
+    scenes: SceneStack,
+    context: EngineContext,
+    update_duration: Duration,
+    max_updates_per_frame: u32,
+}
+
+impl BlightCore {
+    /// Create an engine with a window and the default settings
+    /// Use `BlightCore::builder` to configure it
+    pub fn new() -> BlightCore {
+        BlightCoreBuilder::new()
+            .build()
+            .expect("The default config is valid")
+    }
+
+    /// Create an engine with the given settings
+    pub fn from_config(config: EngineConfig) -> Result<BlightCore, ConfigError> {
+        BlightCoreBuilder::from_config(config).build()
+    }
+
+    pub fn builder() -> BlightCoreBuilder {
+        BlightCoreBuilder::new()
+    }
+
+    fn from_systems(
+        config: EngineConfig,
+        update_duration: Duration,
+        render_system: RenderSystem,
+        input_system: InputSystem,
+    ) -> BlightCore {
+        BlightCore {
+            scenes: SceneStack::new(),
+            context: EngineContext::new(&config, render_system, input_system),
+            update_duration: update_duration,
+            max_updates_per_frame: config.max_updates_per_frame,
+        }
+    }
+
+    pub fn get_context(&self) -> &EngineContext {
+        &self.context
+    }
+
+    pub fn get_context_mut(&mut self) -> &mut EngineContext {
+        &mut self.context
+    }
+
+    pub fn get_scenes(&self) -> &SceneStack {
+        &self.scenes
+    }
+
+    pub fn run<TGame>(&mut self, game: &mut TGame)
+    where
+        TGame: Game,
+    {
+        self.context.start();
+        let mut previous = Instant::now();
+        let update_duration = self.update_duration;
+        let mut lag = Duration::from_millis(0);
+        while self.context.is_running() {
+            let frame_start = Instant::now();
+            let elapsed = frame_start.duration_since(previous);
+            previous = frame_start;
+
+            let paused = self.context.is_paused();
+            let time_scale = self.context.get_time_scale();
+            let (updates, remaining, dropped) = if paused {
+                (
+                    self.context.take_queued_steps(),
+                    Duration::from_secs(0),
+                    Duration::from_secs(0),
+                )
+            } else {
+                let scaled = elapsed.mul_f32(time_scale);
+                catch_up(lag + scaled, update_duration, self.max_updates_per_frame)
+            };
+            lag = remaining;
+            if updates == 0 && (paused || time_scale == 0.) {
+                // The simulation is stopped, keep handling input for the game's render callback
+                self.update_input(game);
+            }
+            let mut timing = FrameTiming {
+                frame_time: elapsed,
+                dropped_time: dropped,
+                ..FrameTiming::default()
+            };
+            while timing.updates < updates && self.context.is_running() {
+                self.tick(game);
+                timing.updates += 1;
+            }
+
+            let render_start = Instant::now();
+            timing.update_time = render_start.duration_since(frame_start);
+            let alpha = if paused {
+                1.0
+            } else {
+                duration_ratio(lag, update_duration)
+            };
+            self.render(game, alpha);
+            timing.render_time = render_start.elapsed();
+            self.context.get_frame_stats_mut().record(timing);
+        }
+    }
+
+    /// Mark the engine as running without entering its loop, so it can be driven by `step`
+    pub fn start(&mut self) {
+        self.context.start();
+    }
+
+    /// Run `updates` fixed updates back to back, ignoring the clock, then render a single frame
+    /// The frame is rendered with an interpolation alpha of 1
+    /// Does nothing unless the engine was started and is still running
+    /// Stops early if the engine is stopped during an update, pausing has no effect on stepping
+    /// Useful to drive a game deterministically, e.g. in tests
+    pub fn step<TGame>(&mut self, game: &mut TGame, updates: usize)
+    where
+        TGame: Game,
+    {
+        if !self.context.is_running() {
+            return;
+        }
+        let frame_start = Instant::now();
+        let mut timing = FrameTiming::default();
+        for _ in 0..updates {
+            if !self.context.is_running() {
+                break;
+            }
+            self.tick(game);
+            timing.updates += 1;
+        }
+        let render_start = Instant::now();
+        timing.update_time = render_start.duration_since(frame_start);
+        self.render(game, 1.0);
+        timing.render_time = render_start.elapsed();
+        timing.frame_time = frame_start.elapsed();
+        self.context.get_frame_stats_mut().record(timing);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.context.is_running()
+    }
+
+    pub fn stop(&mut self) {
+        self.context.stop();
+    }
+
+    fn tick<TGame>(&mut self, game: &mut TGame)
+    where
+        TGame: Game,
+    {
+        self.context.set_delta_time(self.update_duration);
+        self.context.get_render_mut().save_positions();
+        self.update_input(game);
+        game.update(&mut self.context);
+        self.scenes.update(&mut self.context);
+        self.context.run_systems();
+        self.scenes.apply_transitions(&mut self.context);
+    }
+
+    fn update_input<TGame>(&mut self, game: &mut TGame)
+    where
+        TGame: Game,
+    {
+        self.context.update_input();
+        if self.context.take_quit_request() && game.on_quit_request(&mut self.context) {
+            self.context.stop();
+        }
+    }
+
+    fn render<TGame>(&mut self, game: &mut TGame, alpha: f32)
+    where
+        TGame: Game,
+    {
+        // Apply the transitions requested outside of updates, e.g. the first scene pushed by
+        // `Game::init`
+        self.scenes.apply_transitions(&mut self.context);
+        game.render(&mut self.context, alpha);
+        self.scenes.render(&mut self.context, alpha);
+        self.context.draw_overlays();
+        self.context.get_render_mut().render_interpolated(alpha);
+    }
+}
+
+/// Calculate `part / whole`
+fn duration_ratio(part: Duration, whole: Duration) -> f32 {
+    (part.as_secs_f64() / whole.as_secs_f64()) as f32
+}
+
+/// Calculate how many fixed updates to run to catch up with `lag`
+/// Returns the number of updates, the remaining lag and the time dropped because it would
+/// need more than `max_updates` updates
+fn catch_up(
+    lag: Duration,
+    update_duration: Duration,
+    max_updates: u32,
+) -> (u32, Duration, Duration) {
+    let step = update_duration.as_nanos();
+    let lag_nanos = lag.as_nanos();
+    let needed = lag_nanos / step;
+    let remaining = Duration::from_nanos((lag_nanos % step) as u64);
+    if needed <= u128::from(max_updates) {
+        (needed as u32, remaining, Duration::from_secs(0))
+    } else {
+        let dropped = Duration::from_nanos(((needed - u128::from(max_updates)) * step) as u64);
+        (max_updates, remaining, dropped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_catch_up_keeps_the_remainder() {
+        let (updates, remaining, dropped) =
+            catch_up(Duration::from_millis(35), Duration::from_millis(10), 5);
+
+        assert_eq!(updates, 3);
+        assert_eq!(remaining, Duration::from_millis(5));
+        assert_eq!(dropped, Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_catch_up_is_capped() {
+        let (updates, remaining, dropped) =
+            catch_up(Duration::from_millis(1004), Duration::from_millis(10), 5);
+
+        assert_eq!(updates, 5);
+        assert_eq!(remaining, Duration::from_millis(4));
+        assert_eq!(dropped, Duration::from_millis(950));
+    }
+
+    #[test]
+    fn test_duration_ratio() {
+        assert_eq!(
+            duration_ratio(Duration::from_millis(5), Duration::from_millis(20)),
+            0.25
+        );
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_update_rates() {
+        for &updates in &[0, 1_000_000_001] {
+            let result = BlightCore::builder()
+                .headless()
+                .updates_per_second(updates)
+                .build();
+
+            assert_eq!(result.err(), Some(ConfigError::UpdateRate(updates)));
+        }
+        assert!(BlightCore::builder()
+            .headless()
+            .updates_per_second(1_000_000_000)
+            .build()
+            .is_ok());
+    }
+
+    fn set_time_scale(scale: f32) {
+        let mut core = BlightCore::builder().headless().build().unwrap();
+        core.get_context_mut().set_time_scale(scale);
+    }
+
+    #[test]
+    fn test_time_scale() {
+        set_time_scale(0.);
+        set_time_scale(2.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_time_scale_can_not_be_negative() {
+        set_time_scale(-1.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_time_scale_can_not_be_infinite() {
+        set_time_scale(f32::INFINITY);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_time_scale_can_not_be_nan() {
+        set_time_scale(f32::NAN);
+    }
+
+    #[test]
+    fn test_build_rejects_no_updates_per_frame() {
+        let result = BlightCore::builder()
+            .headless()
+            .max_updates_per_frame(0)
+            .build();
+
+        assert_eq!(result.err(), Some(ConfigError::NoUpdatesPerFrame));
+    }
+}
@@ -0,0 +1,407 @@
+use super::super::systems::render::GLOBAL_SCOPE;
+use super::EngineContext;
+
+/// A state of the game, e.g. the title screen, the gameplay or a pause menu
+/// Scenes are kept on a stack by the `BlightCore`, only the top one is active unless it lets
+/// the scenes below it update or render
+///
+/// Render components created in a scene's callbacks belong to that scene, they are hidden
+/// while the scene is covered and deleted when it is popped
+///```no_run
+/// use blight::core::{EngineContext, Scene};
+///
+/// struct PauseMenu;
+///
+/// impl Scene for PauseMenu {
+///     fn update(&mut self, ctx: &mut EngineContext) {
+///         if ctx.get_input().iter_events().count() > 0 {
+///             ctx.pop_scene();
+///         }
+///     }
+///
+///     // Keep drawing the gameplay under the menu
+///     fn render_below(&self) -> bool {
+///         true
+///     }
+/// }
+///```
+pub trait Scene {
+    /// Called when the scene is pushed onto the stack
+    fn on_enter(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Called when the scene is popped or replaced, right before it is dropped
+    fn on_exit(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Called when another scene is pushed on top of this one
+    fn on_pause(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Called when the scene on top of this one is popped
+    fn on_resume(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Called on every fixed update while the scene is active
+    fn update(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Called once per frame while the scene is visible, see `Game::render`
+    fn render(&mut self, _ctx: &mut EngineContext, _alpha: f32) {}
+
+    /// Keep updating the scene below this one, e.g. for a HUD over the gameplay
+    fn update_below(&self) -> bool {
+        false
+    }
+
+    /// Keep rendering the scene below this one, e.g. for an overlay menu
+    fn render_below(&self) -> bool {
+        false
+    }
+}
+
+/// Change of the scene stack requested through the `EngineContext`
+pub(crate) enum SceneTransition {
+    Push(Box<dyn Scene>),
+    Pop,
+    Replace(Box<dyn Scene>),
+}
+
+struct SceneEntry {
+    id: usize,
+    scene: Box<dyn Scene>,
+}
+
+/// The scenes of the game, the last one is on top
+/// Transitions are requested via `EngineContext::push_scene`, `pop_scene` and `replace_scene`
+/// and applied by the engine between updates
+pub struct SceneStack {
+    scenes: Vec<SceneEntry>,
+    next_id: usize,
+}
+
+impl SceneStack {
+    pub fn new() -> SceneStack {
+        SceneStack {
+            scenes: vec![],
+            next_id: GLOBAL_SCOPE,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Update the top scene and the scenes it lets update
+    pub(crate) fn update(&mut self, ctx: &mut EngineContext) {
+        let start = self.first_active(|scene| scene.update_below());
+        for entry in self.scenes[start..].iter_mut() {
+            with_scope(ctx, entry, |scene, ctx| scene.update(ctx));
+        }
+    }
+
+    /// Render the top scene and the scenes it lets render, hiding the components of the rest
+    pub(crate) fn render(&mut self, ctx: &mut EngineContext, alpha: f32) {
+        let start = self.first_active(|scene| scene.render_below());
+        for (index, entry) in self.scenes.iter().enumerate() {
+            ctx.get_render_mut()
+                .set_scope_visible(entry.id, index >= start);
+        }
+        for entry in self.scenes[start..].iter_mut() {
+            with_scope(ctx, entry, |scene, ctx| scene.render(ctx, alpha));
+        }
+    }
+
+    /// Apply the transitions requested since the last call, in the order they were requested
+    pub(crate) fn apply_transitions(&mut self, ctx: &mut EngineContext) {
+        // Hooks may request further transitions
+        loop {
+            let transitions = ctx.take_scene_transitions();
+            if transitions.is_empty() {
+                break;
+            }
+            for transition in transitions {
+                match transition {
+                    SceneTransition::Push(scene) => self.push(ctx, scene),
+                    SceneTransition::Pop => {
+                        if self.pop(ctx) {
+                            if let Some(entry) = self.scenes.last_mut() {
+                                with_scope(ctx, entry, |scene, ctx| scene.on_resume(ctx));
+                            }
+                        }
+                    }
+                    // The scene below stays paused, it was paused when the replaced one was pushed
+                    SceneTransition::Replace(scene) => {
+                        self.pop(ctx);
+                        self.enter(ctx, scene);
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, ctx: &mut EngineContext, scene: Box<dyn Scene>) {
+        if let Some(entry) = self.scenes.last_mut() {
+            with_scope(ctx, entry, |scene, ctx| scene.on_pause(ctx));
+        }
+        self.enter(ctx, scene);
+    }
+
+    /// Put `scene` on top without pausing the current top
+    fn enter(&mut self, ctx: &mut EngineContext, scene: Box<dyn Scene>) {
+        self.next_id += 1;
+        let mut entry = SceneEntry {
+            id: self.next_id,
+            scene: scene,
+        };
+        with_scope(ctx, &mut entry, |scene, ctx| scene.on_enter(ctx));
+        self.scenes.push(entry);
+    }
+
+    /// Returns whether there was a scene to pop
+    fn pop(&mut self, ctx: &mut EngineContext) -> bool {
+        match self.scenes.pop() {
+            Some(mut entry) => {
+                with_scope(ctx, &mut entry, |scene, ctx| scene.on_exit(ctx));
+                let id = entry.id;
+                // Drop the scene first so its components are deleted by their own proxies
+                drop(entry);
+                ctx.get_render_mut().purge_scope(id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Index of the lowest scene that is still reached from the top through `see_through`
+    fn first_active<F>(&self, see_through: F) -> usize
+    where
+        F: Fn(&dyn Scene) -> bool,
+    {
+        let mut start = self.scenes.len();
+        while start > 0 {
+            start -= 1;
+            if !see_through(&*self.scenes[start].scene) {
+                break;
+            }
+        }
+        start
+    }
+}
+
+impl Default for SceneStack {
+    fn default() -> Self {
+        SceneStack::new()
+    }
+}
+
+/// Run `callback` with the scene's render scope set
+fn with_scope<F>(ctx: &mut EngineContext, entry: &mut SceneEntry, callback: F)
+where
+    F: FnOnce(&mut dyn Scene, &mut EngineContext),
+{
+    let previous = ctx.get_render_mut().set_scope(entry.id);
+    callback(&mut *entry.scene, ctx);
+    ctx.get_render_mut().set_scope(previous);
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::testing::GameHarness;
+    use super::super::super::Game;
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct Director {
+        log: Log,
+    }
+
+    impl Game for Director {
+        fn init(_ctx: &mut EngineContext) -> Self {
+            Director {
+                log: Rc::new(RefCell::new(vec![])),
+            }
+        }
+    }
+
+    struct Logged {
+        name: &'static str,
+        log: Log,
+        overlay: bool,
+    }
+
+    impl Logged {
+        fn record(&self, hook: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", self.name, hook));
+        }
+    }
+
+    impl Scene for Logged {
+        fn on_enter(&mut self, ctx: &mut EngineContext) {
+            self.record("enter");
+            // Release the component, it is still deleted on pop
+            ctx.get_render_mut().create_component().release();
+        }
+
+        fn on_exit(&mut self, _ctx: &mut EngineContext) {
+            self.record("exit");
+        }
+
+        fn on_pause(&mut self, _ctx: &mut EngineContext) {
+            self.record("pause");
+        }
+
+        fn on_resume(&mut self, _ctx: &mut EngineContext) {
+            self.record("resume");
+        }
+
+        fn update(&mut self, _ctx: &mut EngineContext) {
+            self.record("update");
+        }
+
+        fn update_below(&self) -> bool {
+            self.overlay
+        }
+
+        fn render_below(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    fn scene(harness: &GameHarness<Director>, name: &'static str, overlay: bool) -> Logged {
+        Logged {
+            name: name,
+            log: harness.game().log.clone(),
+            overlay: overlay,
+        }
+    }
+
+    fn take_log(harness: &GameHarness<Director>) -> Vec<String> {
+        harness.game().log.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn test_transitions_call_the_hooks_in_order() {
+        let mut harness = GameHarness::<Director>::new();
+        let (title, game, menu) = (
+            scene(&harness, "title", false),
+            scene(&harness, "game", false),
+            scene(&harness, "menu", false),
+        );
+
+        harness.engine_mut().get_context_mut().push_scene(title);
+        harness.step(0);
+        harness.engine_mut().get_context_mut().replace_scene(game);
+        harness.step(0);
+        harness.engine_mut().get_context_mut().push_scene(menu);
+        harness.step(0);
+        harness.engine_mut().get_context_mut().pop_scene();
+        harness.step(0);
+
+        assert_eq!(
+            take_log(&harness),
+            vec![
+                "title enter",
+                "title exit",
+                "game enter",
+                "game pause",
+                "menu enter",
+                "menu exit",
+                "game resume",
+            ]
+        );
+        assert_eq!(harness.engine().get_scenes().len(), 1);
+    }
+
+    #[test]
+    fn test_replacing_keeps_the_scene_below_paused() {
+        let mut harness = GameHarness::<Director>::new();
+        let (game, menu, options) = (
+            scene(&harness, "game", false),
+            scene(&harness, "menu", false),
+            scene(&harness, "options", false),
+        );
+
+        harness.engine_mut().get_context_mut().push_scene(game);
+        harness.engine_mut().get_context_mut().push_scene(menu);
+        harness.step(0);
+        harness
+            .engine_mut()
+            .get_context_mut()
+            .replace_scene(options);
+        harness.step(0);
+        harness.engine_mut().get_context_mut().pop_scene();
+        harness.step(0);
+
+        let log = take_log(&harness);
+        let game: Vec<&String> = log.iter().filter(|hook| hook.starts_with("game")).collect();
+        assert_eq!(game, vec!["game enter", "game pause", "game resume"]);
+        assert_eq!(
+            log,
+            vec![
+                "game enter",
+                "game pause",
+                "menu enter",
+                "menu exit",
+                "options enter",
+                "options exit",
+                "game resume",
+            ]
+        );
+        assert_eq!(harness.engine().get_scenes().len(), 1);
+    }
+
+    #[test]
+    fn test_only_the_top_scene_updates_unless_it_is_an_overlay() {
+        let mut harness = GameHarness::<Director>::new();
+        let (game, menu, hud) = (
+            scene(&harness, "game", false),
+            scene(&harness, "menu", false),
+            scene(&harness, "hud", true),
+        );
+        harness.engine_mut().get_context_mut().push_scene(game);
+        harness.engine_mut().get_context_mut().push_scene(menu);
+        harness.step(0);
+        take_log(&harness);
+
+        harness.step(1);
+        assert_eq!(take_log(&harness), vec!["menu update"]);
+
+        harness.engine_mut().get_context_mut().replace_scene(hud);
+        harness.step(0);
+        take_log(&harness);
+
+        harness.step(1);
+        assert_eq!(take_log(&harness), vec!["game update", "hud update"]);
+    }
+
+    #[test]
+    fn test_components_are_hidden_while_covered_and_deleted_on_pop() {
+        let mut harness = GameHarness::<Director>::new();
+        let (game, menu) = (
+            scene(&harness, "game", false),
+            scene(&harness, "menu", false),
+        );
+        harness.engine_mut().get_context_mut().push_scene(game);
+        harness.engine_mut().get_context_mut().push_scene(menu);
+        harness.step(0);
+
+        {
+            let render = harness.engine().get_context().get_render();
+            assert_eq!(render.component_count(), 2);
+            assert!(!render.is_scope_visible(1));
+            assert!(render.is_scope_visible(2));
+        }
+
+        harness.engine_mut().get_context_mut().pop_scene();
+        harness.step(0);
+
+        let render = harness.engine().get_context().get_render();
+        assert_eq!(render.component_count(), 1);
+        assert!(render.is_scope_visible(1));
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of frames the averages and percentiles are calculated from
+pub const FRAME_HISTORY: usize = 120;
+
+/// Timing of a single frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameTiming {
+    /// Time since the start of the previous frame
+    pub frame_time: Duration,
+    /// Time spent running fixed updates
+    pub update_time: Duration,
+    /// Time spent rendering
+    pub render_time: Duration,
+    /// Number of fixed updates ran
+    pub updates: u32,
+    /// Time the engine fell behind by and did not simulate
+    pub dropped_time: Duration,
+}
+
+/// Timing statistics of the frames ran by the engine
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    history: VecDeque<FrameTiming>,
+    frame_count: u64,
+    update_count: u64,
+    dropped_time: Duration,
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        FrameStats {
+            history: VecDeque::with_capacity(FRAME_HISTORY),
+            frame_count: 0,
+            update_count: 0,
+            dropped_time: Duration::from_secs(0),
+        }
+    }
+
+    pub fn record(&mut self, timing: FrameTiming) {
+        if self.history.len() == FRAME_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(timing);
+        self.frame_count += 1;
+        self.update_count += u64::from(timing.updates);
+        self.dropped_time += timing.dropped_time;
+    }
+
+    pub fn last_frame(&self) -> Option<&FrameTiming> {
+        self.history.back()
+    }
+
+    /// Number of frames rendered in total
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Number of fixed updates ran in total
+    pub fn update_count(&self) -> u64 {
+        self.update_count
+    }
+
+    /// Total time the engine did not simulate because it fell too far behind
+    pub fn dropped_time(&self) -> Duration {
+        self.dropped_time
+    }
+
+    /// Frames per second over the recent frames
+    pub fn fps(&self) -> f32 {
+        let total: Duration = self.history.iter().map(|frame| frame.frame_time).sum();
+        let nanos = total.as_nanos() as f64;
+        if nanos > 0. {
+            (self.history.len() as f64 * 1e9 / nanos) as f32
+        } else {
+            0.
+        }
+    }
+
+    pub fn average_frame_time(&self) -> Duration {
+        self.average(|frame| frame.frame_time)
+    }
+
+    pub fn average_update_time(&self) -> Duration {
+        self.average(|frame| frame.update_time)
+    }
+
+    pub fn average_render_time(&self) -> Duration {
+        self.average(|frame| frame.render_time)
+    }
+
+    /// Frame time that `percentile` of the recent frames did not exceed
+    /// `percentile` is in the range [0, 1], e.g. 0.99 yields the 99th percentile
+    pub fn frame_time_percentile(&self, percentile: f32) -> Duration {
+        assert!((0. ..=1.).contains(&percentile));
+        if self.history.is_empty() {
+            return Duration::from_secs(0);
+        }
+        let mut times: Vec<Duration> = self.history.iter().map(|frame| frame.frame_time).collect();
+        times.sort();
+        let rank = (percentile * times.len() as f32).ceil() as usize;
+        times[rank.max(1) - 1]
+    }
+
+    pub fn reset(&mut self) {
+        *self = FrameStats::new();
+    }
+
+    fn average<F>(&self, field: F) -> Duration
+    where
+        F: Fn(&FrameTiming) -> Duration,
+    {
+        if self.history.is_empty() {
+            return Duration::from_secs(0);
+        }
+        let total: Duration = self.history.iter().map(field).sum();
+        total / self.history.len() as u32
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        FrameStats::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frame(millis: u64) -> FrameTiming {
+        FrameTiming {
+            frame_time: Duration::from_millis(millis),
+            update_time: Duration::from_millis(millis / 2),
+            render_time: Duration::from_millis(millis / 5),
+            updates: 1,
+            dropped_time: Duration::from_secs(0),
+        }
+    }
+
+    #[test]
+    fn test_empty_stats() {
+        let stats = FrameStats::new();
+
+        assert_eq!(stats.fps(), 0.);
+        assert_eq!(stats.average_frame_time(), Duration::from_secs(0));
+        assert_eq!(stats.frame_time_percentile(0.99), Duration::from_secs(0));
+        assert!(stats.last_frame().is_none());
+    }
+
+    #[test]
+    fn test_averages() {
+        let mut stats = FrameStats::new();
+        stats.record(frame(10));
+        stats.record(frame(30));
+
+        assert_eq!(stats.fps(), 50.);
+        assert_eq!(stats.average_frame_time(), Duration::from_millis(20));
+        assert_eq!(stats.average_update_time(), Duration::from_millis(10));
+        assert_eq!(stats.average_render_time(), Duration::from_millis(4));
+        assert_eq!(stats.frame_count(), 2);
+        assert_eq!(stats.update_count(), 2);
+        assert_eq!(*stats.last_frame().unwrap(), frame(30));
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut stats = FrameStats::new();
+        for millis in (1..101).rev() {
+            stats.record(frame(millis));
+        }
+
+        assert_eq!(stats.frame_time_percentile(0.), Duration::from_millis(1));
+        assert_eq!(stats.frame_time_percentile(0.5), Duration::from_millis(50));
+        assert_eq!(stats.frame_time_percentile(0.99), Duration::from_millis(99));
+        assert_eq!(stats.frame_time_percentile(1.), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_history_is_limited_but_totals_are_not() {
+        let mut stats = FrameStats::new();
+        for _ in 0..FRAME_HISTORY {
+            stats.record(frame(100));
+        }
+        for _ in 0..FRAME_HISTORY {
+            let mut timing = frame(10);
+            timing.dropped_time = Duration::from_millis(1);
+            stats.record(timing);
+        }
+
+        assert_eq!(stats.average_frame_time(), Duration::from_millis(10));
+        assert_eq!(stats.frame_count(), 2 * FRAME_HISTORY as u64);
+        assert_eq!(
+            stats.dropped_time(),
+            Duration::from_millis(FRAME_HISTORY as u64)
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use super::super::common::arena::Index;
+
+/// Identifies a game object in the `World`
+/// Ids of deleted entities are reused with a new generation, so stale ids never alias a new
+/// entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity(Index);
+
+impl Entity {
+    pub(crate) fn new(index: Index) -> Entity {
+        Entity(index)
+    }
+
+    pub(crate) fn get_handle(&self) -> Index {
+        self.0
+    }
+
+    /// Slot of the entity, shared by the entities that were deleted before it
+    pub fn get_index(&self) -> usize {
+        self.0.get_index()
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        self.0.get_generation()
+    }
+}
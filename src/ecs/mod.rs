@@ -0,0 +1,12 @@
+//! Entity-component-system
+//! Entities are ids, components are plain data attached to them in the `World` and systems
+//! are scheduled by the engine to run on every fixed update
+mod entity;
+mod schedule;
+mod storage;
+mod world;
+
+pub use self::entity::Entity;
+pub use self::schedule::{Schedule, System};
+pub use self::storage::Storage;
+pub use self::world::World;
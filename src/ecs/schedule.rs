@@ -0,0 +1,136 @@
+use super::super::core::EngineContext;
+
+/// Logic ran on the `World` every fixed update
+/// Closures taking the `EngineContext` are systems too
+pub trait System {
+    fn run(&mut self, ctx: &mut EngineContext);
+}
+
+impl<F> System for F
+where
+    F: FnMut(&mut EngineContext),
+{
+    fn run(&mut self, ctx: &mut EngineContext) {
+        self(ctx)
+    }
+}
+
+/// Systems ran by the engine, in the order they were added
+pub struct Schedule {
+    systems: Vec<Box<dyn System>>,
+}
+
+impl Schedule {
+    pub fn new() -> Schedule {
+        Schedule { systems: vec![] }
+    }
+
+    pub fn add_system<S>(&mut self, system: S)
+    where
+        S: System + 'static,
+    {
+        self.systems.push(Box::new(system));
+    }
+
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    pub fn run(&mut self, ctx: &mut EngineContext) {
+        for system in self.systems.iter_mut() {
+            system.run(ctx);
+        }
+    }
+
+    /// Move the systems of `other` to the end of `self`
+    pub fn append(&mut self, other: &mut Schedule) {
+        self.systems.append(&mut other.systems);
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::common::aabb::AABB;
+    use super::super::super::common::vector2::Vector2;
+    use super::super::super::components::position::Position;
+    use super::super::super::systems::collision::Collider;
+    use super::super::super::systems::render::RenderComponent;
+    use super::super::super::testing::GameHarness;
+    use super::super::super::Game;
+    use super::super::Entity;
+    use super::*;
+
+    struct Velocity(f32);
+
+    struct Mover {
+        entity: Entity,
+    }
+
+    impl Game for Mover {
+        fn init(ctx: &mut EngineContext) -> Self {
+            let component = ctx.get_render_mut().create_component();
+            let world = ctx.get_world_mut();
+            let entity = world.create_entity();
+            world.add_component(entity, Position::new(100., 100.));
+            world.add_component(entity, Velocity(10.));
+            world.add_component(entity, component);
+            world.add_component(
+                entity,
+                Collider::AABB(AABB::new(Vector2::new(0., 0.), 10., 10.)),
+            );
+
+            ctx.add_system(|ctx: &mut EngineContext| {
+                ctx.get_world_mut()
+                    .for_each2::<Position, Velocity, _>(|_, position, velocity| {
+                        position.0.x += velocity.0;
+                    });
+            });
+            Mover { entity: entity }
+        }
+    }
+
+    #[test]
+    fn test_systems_run_every_update_and_move_the_components() {
+        let mut harness = GameHarness::<Mover>::new();
+
+        harness.step(3);
+
+        let entity = harness.game().entity;
+        let ctx = harness.engine().get_context();
+        let world = ctx.get_world();
+        assert_eq!(world.get_component::<Position>(entity).unwrap().0.x, 130.);
+        let component = world.get_component::<RenderComponent>(entity).unwrap();
+        let component = ctx.get_render().try_get(component).unwrap();
+        assert_eq!(component.position.center().x(), 130);
+        let found = ctx
+            .get_collision()
+            .query_range(AABB::new(Vector2::new(130., 100.), 2., 2.));
+        assert_eq!(found, vec![entity]);
+    }
+
+    #[test]
+    fn test_systems_added_while_running_are_kept() {
+        let mut harness = GameHarness::<Mover>::new();
+        harness
+            .engine_mut()
+            .get_context_mut()
+            .add_system(|ctx: &mut EngineContext| {
+                ctx.add_system(|_: &mut EngineContext| {});
+            });
+
+        harness.step(2);
+
+        // The mover, the system adding systems and the two systems it added
+        assert_eq!(harness.engine().get_context().get_system_count(), 4);
+    }
+}
@@ -0,0 +1,90 @@
+use super::Entity;
+
+/// Components of a single type, indexed by their entity
+#[derive(Debug)]
+pub struct Storage<T> {
+    components: Vec<Option<(Entity, T)>>,
+    len: usize,
+}
+
+impl<T> Storage<T> {
+    pub fn new() -> Storage<T> {
+        Storage {
+            components: vec![],
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Attach `component` to `entity`, returns the component it replaced
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        let index = entity.get_index();
+        if index >= self.components.len() {
+            self.components.resize_with(index + 1, || None);
+        }
+        let previous = self.components[index].replace((entity, component));
+        match previous {
+            Some((owner, component)) if owner == entity => Some(component),
+            Some(_) => None,
+            None => {
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        if !self.contains(entity) {
+            return None;
+        }
+        self.len -= 1;
+        self.components[entity.get_index()]
+            .take()
+            .map(|(_, component)| component)
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.components.get(entity.get_index()) {
+            Some(Some((owner, component))) if *owner == entity => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.components.get_mut(entity.get_index()) {
+            Some(Some((owner, component))) if *owner == entity => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.components.iter().filter_map(|slot| {
+            slot.as_ref()
+                .map(|(entity, component)| (*entity, component))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.components.iter_mut().filter_map(|slot| {
+            slot.as_mut()
+                .map(|(entity, component)| (*entity, component))
+        })
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Storage::new()
+    }
+}
@@ -0,0 +1,331 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use super::super::common::arena::Arena;
+use super::{Entity, Storage};
+
+/// Type erased `Storage`, lets the `World` clean up the components of deleted entities
+trait AnyStorage {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T> AnyStorage for Storage<T>
+where
+    T: 'static,
+{
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Owns the entities of the game and their components
+/// Any `'static` type can be used as a component, each entity has at most one component of
+/// a type
+///```
+/// use blight::ecs::World;
+///
+/// struct Health(u32);
+/// struct Poisoned;
+///
+/// let mut world = World::new();
+/// let player = world.create_entity();
+/// world.add_component(player, Health(10));
+/// world.add_component(player, Poisoned);
+///
+/// world.for_each2::<Health, Poisoned, _>(|_, health, _| health.0 -= 1);
+///
+/// assert_eq!(world.get_component::<Health>(player).unwrap().0, 9);
+///```
+pub struct World {
+    entities: Arena<()>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    pub fn new() -> World {
+        World {
+            entities: Arena::new(),
+            storages: HashMap::new(),
+        }
+    }
+
+    pub fn create_entity(&mut self) -> Entity {
+        Entity::new(self.entities.insert(()))
+    }
+
+    /// Delete `entity` and all of its components
+    /// Returns `false` if the entity was already deleted
+    pub fn delete_entity(&mut self, entity: Entity) -> bool {
+        if self.entities.remove(entity.get_handle()).is_none() {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.contains(entity.get_handle())
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn iter_entities<'a>(&'a self) -> impl Iterator<Item = Entity> + 'a {
+        self.entities.iter().map(|(index, _)| Entity::new(index))
+    }
+
+    /// Attach `component` to `entity`, returns the component of the same type it replaced
+    pub fn add_component<T>(&mut self, entity: Entity, component: T) -> Option<T>
+    where
+        T: 'static,
+    {
+        assert!(
+            self.is_alive(entity),
+            "Can not add a component to a deleted entity"
+        );
+        self.get_storage_mut::<T>().insert(entity, component)
+    }
+
+    pub fn remove_component<T>(&mut self, entity: Entity) -> Option<T>
+    where
+        T: 'static,
+    {
+        self.get_storage_mut::<T>().remove(entity)
+    }
+
+    pub fn has_component<T>(&self, entity: Entity) -> bool
+    where
+        T: 'static,
+    {
+        self.get_component::<T>(entity).is_some()
+    }
+
+    pub fn get_component<T>(&self, entity: Entity) -> Option<&T>
+    where
+        T: 'static,
+    {
+        self.get_storage::<T>()
+            .and_then(|storage| storage.get(entity))
+    }
+
+    pub fn get_component_mut<T>(&mut self, entity: Entity) -> Option<&mut T>
+    where
+        T: 'static,
+    {
+        self.get_storage_mut::<T>().get_mut(entity)
+    }
+
+    /// The components of type `T`, `None` if no such component was ever added
+    pub fn get_storage<T>(&self) -> Option<&Storage<T>>
+    where
+        T: 'static,
+    {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+
+    pub fn get_storage_mut<T>(&mut self) -> &mut Storage<T>
+    where
+        T: 'static,
+    {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T>::new()))
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Run `callback` on every entity having a `A` component
+    pub fn for_each<A, F>(&mut self, mut callback: F)
+    where
+        A: 'static,
+        F: FnMut(Entity, &mut A),
+    {
+        for (entity, a) in self.get_storage_mut::<A>().iter_mut() {
+            callback(entity, a);
+        }
+    }
+
+    /// Run `callback` on every entity having both `A` and `B` components
+    pub fn for_each2<A, B, F>(&mut self, mut callback: F)
+    where
+        A: 'static,
+        B: 'static,
+        F: FnMut(Entity, &mut A, &mut B),
+    {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "Query types must differ"
+        );
+        // Take ownership of the storages so they can be borrowed at the same time
+        let mut b = self.take_storage::<B>();
+        for (entity, a) in self.get_storage_mut::<A>().iter_mut() {
+            if let Some(b) = b.get_mut(entity) {
+                callback(entity, a, b);
+            }
+        }
+        self.return_storage(b);
+    }
+
+    /// Run `callback` on every entity having `A`, `B` and `C` components
+    pub fn for_each3<A, B, C, F>(&mut self, mut callback: F)
+    where
+        A: 'static,
+        B: 'static,
+        C: 'static,
+        F: FnMut(Entity, &mut A, &mut B, &mut C),
+    {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<C>(),
+            "Query types must differ"
+        );
+        assert_ne!(
+            TypeId::of::<B>(),
+            TypeId::of::<C>(),
+            "Query types must differ"
+        );
+        let mut c = self.take_storage::<C>();
+        self.for_each2::<A, B, _>(|entity, a, b| {
+            if let Some(c) = c.get_mut(entity) {
+                callback(entity, a, b, c);
+            }
+        });
+        self.return_storage(c);
+    }
+
+    /// Entities having both `A` and `B` components
+    pub fn query2<A, B>(&self) -> Vec<Entity>
+    where
+        A: 'static,
+        B: 'static,
+    {
+        match (self.get_storage::<A>(), self.get_storage::<B>()) {
+            (Some(a), Some(b)) => a
+                .iter()
+                .map(|(entity, _)| entity)
+                .filter(|entity| b.contains(*entity))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn take_storage<T>(&mut self) -> Box<Storage<T>>
+    where
+        T: 'static,
+    {
+        match self.storages.remove(&TypeId::of::<T>()) {
+            Some(storage) => storage.into_any().downcast().unwrap(),
+            None => Box::new(Storage::new()),
+        }
+    }
+
+    fn return_storage<T>(&mut self, storage: Box<Storage<T>>)
+    where
+        T: 'static,
+    {
+        self.storages.insert(TypeId::of::<T>(), storage);
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(i32);
+    #[derive(Debug, PartialEq)]
+    struct Velocity(i32);
+    struct Frozen;
+
+    #[test]
+    fn test_components_are_attached_to_entities() {
+        let mut world = World::new();
+        let a = world.create_entity();
+        let b = world.create_entity();
+
+        world.add_component(a, Position(1));
+        world.add_component(b, Velocity(2));
+
+        assert_eq!(world.get_component::<Position>(a), Some(&Position(1)));
+        assert!(world.get_component::<Position>(b).is_none());
+        assert!(world.has_component::<Velocity>(b));
+        assert_eq!(world.add_component(a, Position(3)), Some(Position(1)));
+        assert_eq!(world.remove_component::<Position>(a), Some(Position(3)));
+        assert!(!world.has_component::<Position>(a));
+    }
+
+    #[test]
+    fn test_deleting_an_entity_deletes_its_components() {
+        let mut world = World::new();
+        let old = world.create_entity();
+        world.add_component(old, Position(1));
+
+        assert!(world.delete_entity(old));
+        assert!(!world.delete_entity(old));
+
+        let new = world.create_entity();
+        assert_eq!(new.get_index(), old.get_index());
+        assert!(!world.is_alive(old));
+        assert!(world.get_component::<Position>(new).is_none());
+        assert_eq!(world.get_storage::<Position>().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_queries_visit_entities_having_every_component() {
+        let mut world = World::new();
+        for i in 0..6 {
+            let entity = world.create_entity();
+            world.add_component(entity, Position(0));
+            if i % 2 == 0 {
+                world.add_component(entity, Velocity(i));
+            }
+            if i % 3 == 0 {
+                world.add_component(entity, Frozen);
+            }
+        }
+
+        world.for_each2::<Position, Velocity, _>(|_, position, velocity| {
+            position.0 += velocity.0;
+        });
+        let mut frozen = 0;
+        world.for_each3::<Position, Velocity, Frozen, _>(|_, _, _, _| frozen += 1);
+
+        let positions: Vec<i32> = world
+            .get_storage::<Position>()
+            .unwrap()
+            .iter()
+            .map(|(_, position)| position.0)
+            .collect();
+        assert_eq!(positions, vec![0, 0, 2, 0, 4, 0]);
+        assert_eq!(frozen, 1);
+        assert_eq!(world.query2::<Velocity, Frozen>().len(), 1);
+    }
+}
@@ -1,7 +1,15 @@
 #![feature(test)]
 #![feature(extern_prelude)]
+extern crate base64;
+extern crate flate2;
+extern crate png;
 extern crate sdl2;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
 extern crate test;
+extern crate xml;
 
 #[cfg(test)]
 extern crate rand;
@@ -9,21 +17,47 @@ extern crate rand;
 pub mod common;
 pub mod components;
 pub mod core;
+pub mod ecs;
 pub mod systems;
+pub mod testing;
 
-use core::BlightCore;
+use core::EngineContext;
 
-pub trait Game<'a> {
-    fn new(engine: *mut BlightCore<'a>) -> Self;
-    fn update(&mut self) -> () {}
+/// Implemented by games run by the `BlightCore`
+/// The game is updated and rendered before its scenes, see `core::Scene`
+pub trait Game {
+    /// Create the game, called once before the engine starts
+    fn init(ctx: &mut EngineContext) -> Self
+    where
+        Self: Sized;
+
+    /// Called on every fixed update
+    fn update(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Called once per frame, before the render system draws its components
+    /// `alpha` is how far the engine is into the next fixed update, in the range [0, 1]
+    /// Use it to interpolate between the previous and the current state of the game
+    fn render(&mut self, _ctx: &mut EngineContext, _alpha: f32) {}
+
+    /// Called when quitting is requested, e.g. the window is closed or the quit key is pressed
+    /// Return `false` to veto quitting, for example to show a confirmation dialog instead
+    fn on_quit_request(&mut self, _ctx: &mut EngineContext) -> bool {
+        true
+    }
 }
 
+/// Generate a `main` function running `$tgame`
+/// Optionally takes an `EngineConfig` expression to configure the engine with
 #[macro_export]
 macro_rules! blight_main {
     ($tgame:ident) => {
+        blight_main!($tgame, $crate::core::EngineConfig::default());
+    };
+    ($tgame:ident, $config:expr) => {
         fn main() {
-            let mut engine = BlightCore::new();
-            let mut game = $tgame::new(&mut engine as *mut BlightCore);
+            let mut engine = $crate::core::BlightCore::from_config($config)
+                .unwrap_or_else(|error| panic!("Invalid engine config: {:?}", error));
+            let mut game = <$tgame as $crate::Game>::init(engine.get_context_mut());
             engine.run(&mut game)
         }
     };
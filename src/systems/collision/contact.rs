@@ -0,0 +1,319 @@
+use super::super::super::common::vector2::Vector2;
+use super::super::render::Shape;
+use super::Collider;
+
+/// A collider moved to the position of its entity
+#[derive(Debug, Clone)]
+pub(crate) enum WorldShape {
+    Circle(Vector2, f32),
+    /// Corners of a convex polygon in order
+    Polygon(Vec<Vector2>),
+}
+
+impl WorldShape {
+    pub fn new(collider: &Collider, position: &Vector2) -> WorldShape {
+        let moved = |points: Vec<Vector2>| {
+            WorldShape::Polygon(points.iter().map(|point| point.add(position)).collect())
+        };
+        match *collider {
+            Collider::AABB(ref aabb) => moved(aabb.polygon(0)),
+            Collider::OBB(ref obb) => moved(obb.polygon(0)),
+            Collider::Polygon(ref points) => moved(points.clone()),
+            Collider::Circle(ref circle) => {
+                WorldShape::Circle(circle.get_center().add(position), circle.get_radius())
+            }
+        }
+    }
+
+    /// Corners of the bounding box of the shape
+    pub fn bounds(&self) -> (Vector2, Vector2) {
+        match *self {
+            WorldShape::Circle(ref center, radius) => (
+                Vector2::new(center.x - radius, center.y - radius),
+                Vector2::new(center.x + radius, center.y + radius),
+            ),
+            WorldShape::Polygon(ref points) => {
+                let mut min = points[0].clone();
+                let mut max = points[0].clone();
+                for point in points.iter() {
+                    min.set(min.x.min(point.x), min.y.min(point.y));
+                    max.set(max.x.max(point.x), max.y.max(point.y));
+                }
+                (min, max)
+            }
+        }
+    }
+
+    /// Test if the shapes overlap
+    /// Returns the contact point, the direction `self` has to move in to separate from
+    /// `other` and how far
+    pub fn collide(&self, other: &WorldShape) -> Option<(Vector2, Vector2, f32)> {
+        match (self, other) {
+            (&WorldShape::Circle(ref a, ra), &WorldShape::Circle(ref b, rb)) => {
+                let offset = a.sub(b);
+                let distance = offset.length();
+                if distance >= ra + rb {
+                    return None;
+                }
+                let normal = normalized(&offset);
+                let point = b.add(&(rb * normal.clone()));
+                Some((point, normal, ra + rb - distance))
+            }
+            (&WorldShape::Circle(ref center, radius), &WorldShape::Polygon(ref polygon)) => {
+                circle_polygon(center, radius, polygon)
+            }
+            (&WorldShape::Polygon(ref polygon), &WorldShape::Circle(ref center, radius)) => {
+                circle_polygon(center, radius, polygon)
+                    .map(|(point, normal, depth)| (point, -1. * normal, depth))
+            }
+            (&WorldShape::Polygon(ref a), &WorldShape::Polygon(ref b)) => polygon_polygon(a, b),
+        }
+    }
+}
+
+fn circle_polygon(
+    center: &Vector2,
+    radius: f32,
+    polygon: &[Vector2],
+) -> Option<(Vector2, Vector2, f32)> {
+    let closest = (0..polygon.len())
+        .map(|i| closest_on_segment(center, &polygon[i], &polygon[(i + 1) % polygon.len()]))
+        .min_by(|a, b| {
+            let (a, b) = (a.sub(center).length(), b.sub(center).length());
+            a.partial_cmp(&b).unwrap()
+        })?;
+    let offset = center.sub(&closest);
+    let distance = offset.length();
+    if contains(polygon, center) {
+        // The circle has to move past the closest edge
+        let normal = if distance > f32::EPSILON {
+            normalized(&(-1. * offset))
+        } else {
+            normalized(&center.sub(&centroid(polygon)))
+        };
+        Some((closest, normal, radius + distance))
+    } else if distance < radius {
+        Some((closest, normalized(&offset), radius - distance))
+    } else {
+        None
+    }
+}
+
+/// Separating axis test of two convex polygons
+fn polygon_polygon(a: &[Vector2], b: &[Vector2]) -> Option<(Vector2, Vector2, f32)> {
+    let mut best: Option<(f32, Vector2)> = None;
+    for polygon in [a, b].iter() {
+        for i in 0..polygon.len() {
+            let edge = polygon[(i + 1) % polygon.len()].sub(&polygon[i]);
+            if edge.length() <= f32::EPSILON {
+                continue;
+            }
+            let axis = normalized(&edge.orthogonal());
+            let (min_a, max_a) = project(a, &axis);
+            let (min_b, max_b) = project(b, &axis);
+            let overlap = max_a.min(max_b) - min_a.max(min_b);
+            if overlap <= 0. {
+                return None;
+            }
+            if best.as_ref().is_none_or(|&(depth, _)| overlap < depth) {
+                best = Some((overlap, axis));
+            }
+        }
+    }
+    let (depth, mut normal) = best?;
+    if centroid(a).sub(&centroid(b)).dot(&normal) < 0. {
+        normal = -1. * normal;
+    }
+    // Approximate the contact with the corners inside the other polygon
+    let inside: Vec<Vector2> = a
+        .iter()
+        .filter(|point| contains(b, point))
+        .chain(b.iter().filter(|point| contains(a, point)))
+        .cloned()
+        .collect();
+    let point = if inside.is_empty() {
+        0.5 * centroid(a).add(&centroid(b))
+    } else {
+        centroid(&inside)
+    };
+    Some((point, normal, depth))
+}
+
+fn normalized(vector: &Vector2) -> Vector2 {
+    let length = vector.length();
+    if length > f32::EPSILON {
+        (1. / length) * vector.clone()
+    } else {
+        Vector2::new(1., 0.)
+    }
+}
+
+fn centroid(points: &[Vector2]) -> Vector2 {
+    let mut sum = Vector2::new(0., 0.);
+    for point in points {
+        sum.add_mut(point);
+    }
+    (1. / points.len() as f32) * sum
+}
+
+fn project(points: &[Vector2], axis: &Vector2) -> (f32, f32) {
+    points
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), point| {
+            let projection = point.dot(axis);
+            (min.min(projection), max.max(projection))
+        })
+}
+
+fn closest_on_segment(point: &Vector2, from: &Vector2, to: &Vector2) -> Vector2 {
+    let segment = to.sub(from);
+    let length = segment.dot(&segment);
+    if length <= f32::EPSILON {
+        return from.clone();
+    }
+    let t = (point.sub(from).dot(&segment) / length).clamp(0., 1.);
+    from.add(&(t * segment))
+}
+
+/// `points` are the corners of a convex polygon in either winding order: there are at least
+/// three, every corner turns the same way and the turns add up to a single revolution so the
+/// edges do not cross
+pub(crate) fn is_convex(points: &[Vector2]) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+    let mut sign = 0.;
+    let mut turns = 0.;
+    for i in 0..points.len() {
+        let edge = points[(i + 1) % points.len()].sub(&points[i]);
+        let next = points[(i + 2) % points.len()].sub(&points[(i + 1) % points.len()]);
+        if edge.length() <= f32::EPSILON {
+            return false;
+        }
+        let cross = edge.x * next.y - edge.y * next.x;
+        turns += cross.atan2(edge.dot(&next));
+        if cross.abs() <= f32::EPSILON {
+            continue;
+        }
+        if sign * cross < 0. {
+            return false;
+        }
+        sign = cross;
+    }
+    sign != 0. && (turns.abs() - 2. * ::std::f32::consts::PI).abs() < 1e-3
+}
+
+/// `point` is inside or on the edge of the convex `polygon`, in either winding order
+fn contains(polygon: &[Vector2], point: &Vector2) -> bool {
+    let mut sign = 0.;
+    for i in 0..polygon.len() {
+        let edge = polygon[(i + 1) % polygon.len()].sub(&polygon[i]);
+        let offset = point.sub(&polygon[i]);
+        let cross = edge.x * offset.y - edge.y * offset.x;
+        if cross.abs() <= f32::EPSILON {
+            continue;
+        }
+        if sign * cross < 0. {
+            return false;
+        }
+        sign = cross;
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::common::aabb::AABB;
+    use super::super::super::super::common::circle::Circle;
+    use super::*;
+
+    fn aabb(x: f32, y: f32, size: f32) -> WorldShape {
+        let collider = Collider::AABB(AABB::new(Vector2::new(0., 0.), size, size));
+        WorldShape::new(&collider, &Vector2::new(x, y))
+    }
+
+    #[test]
+    fn test_boxes_separate_along_the_smallest_overlap() {
+        let (point, normal, depth) = aabb(0., 0., 10.).collide(&aabb(8., 1., 10.)).unwrap();
+
+        assert_eq!(normal, Vector2::new(-1., 0.));
+        assert!((depth - 2.).abs() < 1e-5);
+        assert!((point.x - 4.).abs() < 1e-5);
+        assert!(aabb(0., 0., 10.).collide(&aabb(10., 0., 10.)).is_none());
+    }
+
+    #[test]
+    fn test_circles() {
+        let circle = |x: f32| {
+            let collider = Collider::Circle(Circle::new(Vector2::new(0., 0.), 2.));
+            WorldShape::new(&collider, &Vector2::new(x, 0.))
+        };
+
+        let (point, normal, depth) = circle(3.).collide(&circle(0.)).unwrap();
+        assert_eq!(
+            (point, normal),
+            (Vector2::new(2., 0.), Vector2::new(1., 0.))
+        );
+        assert!((depth - 1.).abs() < 1e-5);
+        assert!(circle(4.).collide(&circle(0.)).is_none());
+
+        // A circle inside a box has to move out through the closest edge
+        let (_, normal, depth) = circle(4.).collide(&aabb(0., 0., 10.)).unwrap();
+        assert_eq!(normal, Vector2::new(1., 0.));
+        assert!((depth - 3.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_polygons_are_moved_to_the_position() {
+        let triangle = Collider::Polygon(vec![
+            Vector2::new(-2., -2.),
+            Vector2::new(2., -2.),
+            Vector2::new(0., 2.),
+        ]);
+        let shape = WorldShape::new(&triangle, &Vector2::new(10., 0.));
+
+        assert_eq!(
+            shape.bounds(),
+            (Vector2::new(8., -2.), Vector2::new(12., 2.))
+        );
+        let (_, normal, depth) = shape.collide(&aabb(10., -3., 4.)).unwrap();
+        assert_eq!(normal, Vector2::new(0., 1.));
+        assert!((depth - 1.).abs() < 1e-5);
+        assert!(shape.collide(&aabb(0., 0., 4.)).is_none());
+    }
+    #[test]
+    fn test_convex_polygons() {
+        let points = |corners: &[(f32, f32)]| -> Vec<Vector2> {
+            corners.iter().map(|&(x, y)| Vector2::new(x, y)).collect()
+        };
+        let square = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let reversed: Vec<_> = square.iter().rev().cloned().collect();
+        let star = [(0., -5.), (3., 4.), (-5., -2.), (5., -2.), (-3., 4.)];
+
+        assert!(is_convex(&points(&square)));
+        assert!(is_convex(&points(&reversed)));
+        assert!(is_convex(&points(&[
+            (0., 0.),
+            (1., 0.),
+            (2., 0.),
+            (1., 1.)
+        ])));
+        assert!(!is_convex(&points(&[])));
+        assert!(!is_convex(&points(&[(0., 0.), (1., 0.)])));
+        assert!(!is_convex(&points(&[(0., 0.), (1., 0.), (2., 0.)])));
+        assert!(!is_convex(&points(&[
+            (0., 0.),
+            (1., 0.),
+            (1., 0.),
+            (0., 1.)
+        ])));
+        assert!(!is_convex(&points(&[
+            (0., 0.),
+            (2., 0.),
+            (1., 1.),
+            (2., 2.),
+            (0., 2.)
+        ])));
+        assert!(!is_convex(&points(&star)));
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+
+use super::super::common::aabb::AABB;
+use super::super::common::circle::Circle;
+use super::super::common::obb::OBB2D;
+use super::super::common::quadtree::{Quadtree, Spacial};
+use super::super::common::vector2::Vector2;
+use super::super::components::position::Position;
+use super::super::ecs::{Entity, World};
+use super::render::Primitives;
+
+mod contact;
+mod overlay;
+pub use self::overlay::CollisionOverlay;
+
+/// Shape of an entity, its center is relative to the entity's `Position`
+#[derive(Debug, Clone)]
+pub enum Collider {
+    AABB(AABB),
+    Circle(Circle),
+    OBB(OBB2D),
+    /// Corners of a convex polygon in order, relative to the entity's `Position`
+    Polygon(Vec<Vector2>),
+}
+
+impl Collider {
+    /// Polygon collider of the `corners` in order, relative to the entity's `Position`
+    /// Returns `None` unless the corners form a convex polygon
+    pub fn polygon(corners: Vec<Vector2>) -> Option<Collider> {
+        if contact::is_convex(&corners) {
+            Some(Collider::Polygon(corners))
+        } else {
+            None
+        }
+    }
+
+    /// The collider can be tracked, polygons have to be convex
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Collider::Polygon(ref corners) => contact::is_convex(corners),
+            _ => true,
+        }
+    }
+}
+
+/// Marks the collider of an entity as a trigger, an area reacting to what enters it
+/// Triggers report contacts like any other collider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trigger;
+
+/// Snapshot of an entity's collider at the time of the last update
+#[derive(Debug, Clone)]
+pub struct CollisionComponent {
+    entity: Entity,
+    pos: Vector2,
+    collider: Collider,
+    trigger: bool,
+    sleeping: bool,
+}
+
+impl CollisionComponent {
+    pub fn get_entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn get_collider(&self) -> &Collider {
+        &self.collider
+    }
+
+    pub fn is_trigger(&self) -> bool {
+        self.trigger
+    }
+
+    /// The entity did not move since the update before the last one
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+}
+
+impl Spacial for CollisionComponent {
+    fn position<'a>(&'a self) -> &'a Vector2 {
+        &self.pos
+    }
+}
+
+/// Two colliders overlapping during the last update
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub first: Entity,
+    pub second: Entity,
+    /// Where the colliders touch, in world space
+    pub point: Vector2,
+    /// Direction `first` has to move in to separate from `second`
+    pub normal: Vector2,
+    /// How far `first` has to move to separate from `second`
+    pub depth: f32,
+}
+
+#[derive(Debug)]
+pub struct CollisionSystem {
+    world: Quadtree<CollisionComponent>,
+    center: Vector2,
+    components: Vec<CollisionComponent>,
+    contacts: Vec<Contact>,
+    colliding: HashSet<Entity>,
+    /// Positions of the entities at the previous update
+    previous: HashMap<Entity, Vector2>,
+    overlay: CollisionOverlay,
+}
+
+impl CollisionSystem {
+    pub fn new(boundary: AABB) -> Self {
+        Self {
+            center: boundary.get_center().clone(),
+            world: Quadtree::new(boundary),
+            components: vec![],
+            contacts: vec![],
+            colliding: HashSet::new(),
+            previous: HashMap::new(),
+            overlay: CollisionOverlay::new(),
+        }
+    }
+
+    /// Sets the center of the world. This does not allocate additional memory
+    pub fn set_center(&mut self, position: Vector2) {
+        self.center = position;
+    }
+
+    /// Sets the boundary of the world, note that this requires reallocating the world's memory
+    pub fn set_boundary(&mut self, boundary: AABB) {
+        self.world = Quadtree::new(boundary);
+    }
+
+    /// Rebuild the world from the entities having both a `Position` and a `Collider`, then
+    /// find the contacts between them
+    /// Entities outside of the boundary or with an invalid collider are not tracked
+    pub fn update(&mut self, world: &World) {
+        self.world.clear();
+        self.components.clear();
+        self.contacts.clear();
+        self.colliding.clear();
+        let previous = ::std::mem::take(&mut self.previous);
+        let (positions, colliders) = match (
+            world.get_storage::<Position>(),
+            world.get_storage::<Collider>(),
+        ) {
+            (Some(positions), Some(colliders)) => (positions, colliders),
+            _ => return,
+        };
+        for (entity, collider) in colliders.iter() {
+            if !collider.is_valid() {
+                continue;
+            }
+            if let Some(position) = positions.get(entity) {
+                let component = CollisionComponent {
+                    entity: entity,
+                    pos: position.0.clone(),
+                    collider: collider.clone(),
+                    trigger: world.has_component::<Trigger>(entity),
+                    sleeping: previous.get(&entity) == Some(&position.0),
+                };
+                if self.world.insert(component.clone()).is_ok() {
+                    self.previous.insert(entity, position.0.clone());
+                    self.components.push(component);
+                }
+            }
+        }
+        self.find_contacts();
+    }
+
+    /// Query the entities positioned inside `range`
+    pub fn query_range(&self, range: AABB) -> Vec<Entity> {
+        self.world
+            .query_range(range)
+            .iter()
+            .map(|component| component.entity)
+            .collect()
+    }
+
+    /// The colliders tracked by the last update
+    pub fn get_components(&self) -> &[CollisionComponent] {
+        &self.components
+    }
+
+    /// The contacts found by the last update, each pair of entities is reported once
+    pub fn get_contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// The entity had a contact during the last update
+    pub fn is_colliding(&self, entity: Entity) -> bool {
+        self.colliding.contains(&entity)
+    }
+
+    /// Settings of the debug overlay, the engine draws it while it is enabled
+    pub fn get_overlay(&self) -> &CollisionOverlay {
+        &self.overlay
+    }
+
+    pub fn get_overlay_mut(&mut self) -> &mut CollisionOverlay {
+        &mut self.overlay
+    }
+
+    /// Queue the colliders, the quadtree's nodes and the contacts as world-space primitives
+    pub fn draw_overlay(&self, primitives: &mut Primitives) {
+        self.overlay.draw(self, primitives);
+    }
+
+    fn find_contacts(&mut self) {
+        let shapes: HashMap<Entity, contact::WorldShape> = self
+            .components
+            .iter()
+            .map(|component| {
+                let shape = contact::WorldShape::new(&component.collider, &component.pos);
+                (component.entity, shape)
+            })
+            .collect();
+        // The quadtree indexes positions, so the query is grown by the farthest a collider
+        // reaches from its position
+        let mut reach = Vector2::new(f32::EPSILON, f32::EPSILON);
+        for component in self.components.iter() {
+            let (min, max) = shapes[&component.entity].bounds();
+            for axis in 0..2 {
+                let pos = component.pos.get(axis);
+                let furthest = (pos - min.get(axis)).max(max.get(axis) - pos);
+                *reach.get_mut(axis) = reach.get(axis).max(furthest);
+            }
+        }
+        for component in self.components.iter() {
+            let shape = &shapes[&component.entity];
+            let (min, max) = shape.bounds();
+            let center = 0.5 * min.add(&max);
+            let range = AABB::from_radius(center.clone(), max.sub(&center).add(&reach));
+            for other in self.world.query_range(range) {
+                if other.entity <= component.entity {
+                    continue;
+                }
+                if let Some((point, normal, depth)) = shape.collide(&shapes[&other.entity]) {
+                    self.colliding.insert(component.entity);
+                    self.colliding.insert(other.entity);
+                    self.contacts.push(Contact {
+                        first: component.entity,
+                        second: other.entity,
+                        point: point,
+                        normal: normal,
+                        depth: depth,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_entities_with_position_and_collider_are_tracked() {
+        let mut world = World::new();
+        let mut spawn = |x: f32, y: f32, collider: bool| {
+            let entity = world.create_entity();
+            world.add_component(entity, Position::new(x, y));
+            if collider {
+                world.add_component(
+                    entity,
+                    Collider::AABB(AABB::new(Vector2::new(0., 0.), 1., 1.)),
+                );
+            }
+            entity
+        };
+        let inside = spawn(10., 10., true);
+        spawn(12., 12., false);
+        spawn(90., 90., true);
+        spawn(500., 500., true);
+
+        let mut system = CollisionSystem::new(AABB::new(Vector2::new(50., 50.), 100., 100.));
+        system.update(&world);
+
+        let found = system.query_range(AABB::new(Vector2::new(10., 10.), 10., 10.));
+        assert_eq!(found, vec![inside]);
+    }
+
+    #[test]
+    fn test_contacts_and_states() {
+        let mut world = World::new();
+        let mut spawn = |x: f32, collider: Collider| {
+            let entity = world.create_entity();
+            world.add_component(entity, Position::new(x, 50.));
+            world.add_component(entity, collider);
+            entity
+        };
+        let wall = spawn(
+            50.,
+            Collider::AABB(AABB::new(Vector2::new(0., 0.), 10., 10.)),
+        );
+        let ball = spawn(58., Collider::Circle(Circle::new(Vector2::new(0., 0.), 4.)));
+        let far = spawn(90., Collider::Circle(Circle::new(Vector2::new(0., 0.), 4.)));
+        world.add_component(far, Trigger);
+        let mut system = CollisionSystem::new(AABB::new(Vector2::new(50., 50.), 100., 100.));
+
+        system.update(&world);
+
+        assert_eq!(system.get_contacts().len(), 1);
+        let contact = &system.get_contacts()[0];
+        assert_eq!((contact.first, contact.second), (wall, ball));
+        assert_eq!(contact.normal, Vector2::new(-1., 0.));
+        assert!((contact.depth - 1.).abs() < 1e-5);
+        assert!(system.is_colliding(ball) && !system.is_colliding(far));
+        assert!(system.get_components().iter().all(|c| !c.is_sleeping()));
+
+        world.get_component_mut::<Position>(ball).unwrap().0.x = 70.;
+        system.update(&world);
+
+        assert!(system.get_contacts().is_empty());
+        let sleeping: Vec<Entity> = system
+            .get_components()
+            .iter()
+            .filter(|component| component.is_sleeping())
+            .map(|component| component.get_entity())
+            .collect();
+        assert_eq!(sleeping, vec![wall, far]);
+        assert!(system
+            .get_components()
+            .iter()
+            .any(|component| component.get_entity() == far && component.is_trigger()));
+    }
+    #[test]
+    fn test_invalid_polygons_are_not_tracked() {
+        let triangle = vec![
+            Vector2::new(-2., -2.),
+            Vector2::new(2., -2.),
+            Vector2::new(0., 2.),
+        ];
+        assert!(Collider::polygon(triangle.clone()).is_some());
+        assert!(Collider::polygon(triangle[..2].to_vec()).is_none());
+
+        let mut world = World::new();
+        let mut spawn = |collider: Collider| {
+            let entity = world.create_entity();
+            world.add_component(entity, Position::new(50., 50.));
+            world.add_component(entity, collider);
+            entity
+        };
+        let valid = spawn(Collider::Polygon(triangle));
+        spawn(Collider::Polygon(vec![]));
+        spawn(Collider::Polygon(vec![Vector2::new(1., 1.)]));
+        let mut system = CollisionSystem::new(AABB::new(Vector2::new(50., 50.), 100., 100.));
+
+        system.update(&world);
+
+        let tracked: Vec<Entity> = system
+            .get_components()
+            .iter()
+            .map(|component| component.get_entity())
+            .collect();
+        assert_eq!(tracked, vec![valid]);
+        assert!(system.get_contacts().is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use super::super::super::common::circle::Circle;
+use super::super::super::common::vector2::Vector2;
+use super::super::render::{Color, Primitives, Space};
+use super::contact::WorldShape;
+use super::{CollisionComponent, CollisionSystem};
+
+/// Debug view of a `CollisionSystem`
+/// Colliders are colored by their state, triggers first, then colliding, then sleeping ones
+#[derive(Debug, Clone)]
+pub struct CollisionOverlay {
+    pub enabled: bool,
+    /// Color of the colliders in none of the other states
+    pub collider_color: Color,
+    pub sleeping_color: Color,
+    pub colliding_color: Color,
+    pub trigger_color: Color,
+    /// Color of the boundaries of the quadtree's nodes
+    pub node_color: Color,
+    /// Color of the contact points and normals
+    pub contact_color: Color,
+    /// Length of the drawn contact normals in world units
+    pub normal_length: f32,
+}
+
+impl CollisionOverlay {
+    pub fn new() -> CollisionOverlay {
+        CollisionOverlay {
+            enabled: false,
+            collider_color: Color::RGB(0, 255, 0),
+            sleeping_color: Color::RGB(0, 128, 255),
+            colliding_color: Color::RGB(255, 0, 0),
+            trigger_color: Color::RGB(255, 255, 0),
+            node_color: Color::RGBA(255, 255, 255, 64),
+            contact_color: Color::RGB(255, 0, 255),
+            normal_length: 16.,
+        }
+    }
+
+    /// Color `component` is drawn with
+    pub fn color_of(&self, system: &CollisionSystem, component: &CollisionComponent) -> Color {
+        if component.trigger {
+            self.trigger_color
+        } else if system.is_colliding(component.entity) {
+            self.colliding_color
+        } else if component.sleeping {
+            self.sleeping_color
+        } else {
+            self.collider_color
+        }
+    }
+
+    pub(crate) fn draw(&self, system: &CollisionSystem, primitives: &mut Primitives) {
+        let space = primitives.set_space(Space::World);
+        for boundary in system.world.boundaries() {
+            primitives.outline(boundary, self.node_color);
+        }
+        for component in system.components.iter() {
+            let color = self.color_of(system, component);
+            match WorldShape::new(&component.collider, &component.pos) {
+                WorldShape::Circle(center, radius) => {
+                    primitives.outline(&Circle::new(center, radius), color)
+                }
+                WorldShape::Polygon(points) => primitives.polygon(&points, color),
+            }
+        }
+        for contact in system.contacts.iter() {
+            let point = &contact.point;
+            let (x, y) = (Vector2::new(2., 0.), Vector2::new(0., 2.));
+            primitives.line(&point.sub(&x), &point.add(&x), self.contact_color);
+            primitives.line(&point.sub(&y), &point.add(&y), self.contact_color);
+            let normal = point.add(&(self.normal_length * contact.normal.clone()));
+            primitives.line(point, &normal, self.contact_color);
+        }
+        primitives.set_space(space);
+    }
+}
+
+impl Default for CollisionOverlay {
+    fn default() -> Self {
+        CollisionOverlay::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::common::aabb::AABB;
+    use super::super::super::super::components::position::Position;
+    use super::super::super::super::ecs::World;
+    use super::super::{Collider, Trigger};
+    use super::*;
+
+    #[test]
+    fn test_draw_colliders_nodes_and_contacts() {
+        let mut world = World::new();
+        let mut spawn = |x: f32, y: f32, collider: Collider| {
+            let entity = world.create_entity();
+            world.add_component(entity, Position::new(x, y));
+            world.add_component(entity, collider);
+            entity
+        };
+        let square = || Collider::AABB(AABB::new(Vector2::new(0., 0.), 10., 10.));
+        let wall = spawn(50., 50., square());
+        let ball = spawn(
+            58.,
+            50.,
+            Collider::Circle(Circle::new(Vector2::new(0., 0.), 4.)),
+        );
+        let rest = spawn(20., 20., square());
+        let sensor = spawn(90., 90., square());
+        world.add_component(sensor, Trigger);
+        let mut system = CollisionSystem::new(AABB::new(Vector2::new(50., 50.), 100., 100.));
+        system.update(&world);
+        world.get_component_mut::<Position>(ball).unwrap().0.x = 57.;
+        let fresh = world.create_entity();
+        world.add_component(fresh, Position::new(20., 80.));
+        world.add_component(fresh, square());
+        system.update(&world);
+        system.get_overlay_mut().enabled = true;
+        let mut primitives = Primitives::new();
+        primitives.set_space(Space::Screen);
+
+        system.draw_overlay(&mut primitives);
+
+        let overlay = system.get_overlay();
+        let queued: Vec<(&[Vector2], Color)> = primitives.iter().collect();
+        let nodes = system.world.boundaries().len();
+        assert!(nodes > 1);
+        assert!(queued[..nodes]
+            .iter()
+            .all(|&(points, color)| points.len() == 4 && color == overlay.node_color));
+
+        let colliders = &queued[nodes..nodes + system.get_components().len()];
+        let expected = |entity| match entity {
+            entity if entity == wall || entity == ball => overlay.colliding_color,
+            entity if entity == rest => overlay.sleeping_color,
+            entity if entity == sensor => overlay.trigger_color,
+            _ => overlay.collider_color,
+        };
+        for (component, &(points, color)) in system.get_components().iter().zip(colliders) {
+            assert_eq!(color, expected(component.get_entity()));
+            let corners = if component.get_entity() == ball {
+                primitives.circle_segments
+            } else {
+                4
+            };
+            assert_eq!(points.len(), corners);
+        }
+        assert_eq!(colliders.len(), 5);
+
+        // A cross on the contact point and its normal
+        let contact = &system.get_contacts()[0];
+        let markers = &queued[nodes + colliders.len()..];
+        assert_eq!(markers.len(), 3);
+        assert!(markers
+            .iter()
+            .all(|&(_, color)| color == overlay.contact_color));
+        let normal = contact
+            .point
+            .add(&(overlay.normal_length * contact.normal.clone()));
+        assert_eq!(markers[2].0, &[contact.point.clone(), normal][..]);
+        assert_eq!(primitives.get_space(), Space::Screen);
+    }
+}
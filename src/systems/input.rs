@@ -1,4 +1,7 @@
 use sdl2::{self, Sdl};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
 use std::slice::Iter;
 
 pub type EventPump = sdl2::EventPump;
@@ -7,26 +10,80 @@ pub use sdl2::event::Event;
 pub use sdl2::keyboard::Keycode;
 pub use sdl2::mouse::MouseButton;
 
+/// Provides the events the `InputSystem` handles each update
+pub trait EventSource {
+    /// Move every pending event into `events`
+    fn poll_events(&mut self, events: &mut Vec<Event>);
+}
+
+impl EventSource for EventPump {
+    fn poll_events(&mut self, events: &mut Vec<Event>) {
+        events.extend(self.poll_iter());
+    }
+}
+
+/// Event source fed by the user instead of the OS
+/// Clones share the same queue, so keep a clone around to inject events into a running engine
+#[derive(Clone, Default)]
+pub struct EventQueue {
+    events: Rc<RefCell<VecDeque<Event>>>,
+}
+
+impl EventQueue {
+    pub fn new() -> EventQueue {
+        EventQueue::default()
+    }
+
+    /// Queue an event, it will be handled on the next input update
+    pub fn push(&self, event: Event) {
+        self.events.borrow_mut().push_back(event);
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.borrow().is_empty()
+    }
+}
+
+impl EventSource for EventQueue {
+    fn poll_events(&mut self, events: &mut Vec<Event>) {
+        events.extend(self.events.borrow_mut().drain(..));
+    }
+}
+
 pub struct InputSystem {
-    event_pump: EventPump,
+    source: Box<dyn EventSource>,
+    injected: Vec<Event>,
     events: Vec<Event>,
 }
 
 impl InputSystem {
     pub fn new(sdl: &Sdl) -> InputSystem {
         let event_pump = sdl.event_pump().unwrap();
-        let result = InputSystem {
-            event_pump: event_pump,
+        InputSystem::from_source(Box::new(event_pump))
+    }
+
+    /// Create an InputSystem reading its events from `source`
+    pub fn from_source(source: Box<dyn EventSource>) -> InputSystem {
+        InputSystem {
+            source: source,
+            injected: vec![],
             events: vec![],
-        };
-        result
+        }
     }
 
     pub fn update(&mut self) {
         self.events.clear();
-        for event in self.event_pump.poll_iter() {
-            self.events.push(event);
-        }
+        self.source.poll_events(&mut self.events);
+        self.events.append(&mut self.injected);
+    }
+
+    /// Inject an event, it will be handled on the next update after the events of the source
+    pub fn push_event(&mut self, event: Event) {
+        self.injected.push(event);
     }
 
     /// Pass in a callback to handle each event in the current frame
@@ -39,3 +96,50 @@ impl InputSystem {
         self.events.iter()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_queued_events_are_handled_on_update() {
+        let queue = EventQueue::new();
+        let mut input = InputSystem::from_source(Box::new(queue.clone()));
+
+        queue.push(Event::Quit { timestamp: 0 });
+        assert_eq!(queue.len(), 1);
+        assert_eq!(input.iter_events().count(), 0);
+
+        input.update();
+
+        assert!(queue.is_empty());
+        assert!(input.iter_events().next() == Some(&Event::Quit { timestamp: 0 }));
+
+        input.update();
+
+        assert_eq!(input.iter_events().count(), 0);
+    }
+
+    #[test]
+    fn test_injected_events_follow_the_source_events() {
+        let queue = EventQueue::new();
+        let mut input = InputSystem::from_source(Box::new(queue.clone()));
+
+        input.push_event(Event::AppTerminating { timestamp: 1 });
+        queue.push(Event::Quit { timestamp: 0 });
+        input.update();
+
+        let events: Vec<&Event> = input.iter_events().collect();
+        assert!(
+            events
+                == vec![
+                    &Event::Quit { timestamp: 0 },
+                    &Event::AppTerminating { timestamp: 1 },
+                ]
+        );
+
+        input.update();
+
+        assert_eq!(input.iter_events().count(), 0);
+    }
+}
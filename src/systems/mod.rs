@@ -1,3 +1,4 @@
 pub mod input;
 pub mod render;
 pub mod collision;
+pub mod tiled;
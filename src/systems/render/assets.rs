@@ -0,0 +1,226 @@
+use png;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::TextureHandle;
+
+/// Pixel format whose bytes are in RGBA order, the layout of an `Image`
+#[cfg(target_endian = "little")]
+pub(crate) const RGBA32: PixelFormatEnum = PixelFormatEnum::ABGR8888;
+#[cfg(target_endian = "big")]
+pub(crate) const RGBA32: PixelFormatEnum = PixelFormatEnum::RGBA8888;
+
+#[derive(Debug)]
+pub enum AssetError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    PngEncoding(png::EncodingError),
+    /// The file is neither a BMP nor a PNG
+    UnsupportedFormat(PathBuf),
+    /// SDL could not decode the file or create the texture
+    Sdl(String),
+}
+
+impl From<io::Error> for AssetError {
+    fn from(error: io::Error) -> Self {
+        AssetError::Io(error)
+    }
+}
+
+impl From<png::DecodingError> for AssetError {
+    fn from(error: png::DecodingError) -> Self {
+        AssetError::Png(error)
+    }
+}
+
+impl From<png::EncodingError> for AssetError {
+    fn from(error: png::EncodingError) -> Self {
+        AssetError::PngEncoding(error)
+    }
+}
+
+/// Shared owner of a texture loaded by `RenderSystem::load_texture`
+/// The texture stays loaded while a clone of the asset is alive, components only referencing
+/// its handle do not keep it loaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureAsset(Rc<TextureHandle>);
+
+impl TextureAsset {
+    pub(crate) fn new(handle: TextureHandle) -> TextureAsset {
+        TextureAsset(Rc::new(handle))
+    }
+
+    pub fn handle(&self) -> TextureHandle {
+        *self.0
+    }
+
+    /// No clone of the asset is alive besides this one
+    pub(crate) fn is_unique(&self) -> bool {
+        Rc::strong_count(&self.0) == 1
+    }
+}
+
+impl<'a> From<&'a TextureAsset> for TextureHandle {
+    fn from(asset: &'a TextureAsset) -> TextureHandle {
+        asset.handle()
+    }
+}
+
+/// Decoded pixels of an image, row by row from the top, 4 bytes per pixel in RGBA order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Load the PNG file at `path`
+    pub fn load_png<P>(path: P) -> Result<Image, AssetError>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        Image::decode_png(BufReader::new(file))
+    }
+
+    /// Decode a PNG, converting every color type and bit depth to 8 bit RGBA
+    pub fn decode_png<R>(reader: R) -> Result<Image, AssetError>
+    where
+        R: Read,
+    {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks(3)
+                .flat_map(|pixel| vec![pixel[0], pixel[1], pixel[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks(2)
+                .flat_map(|pixel| vec![pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect(),
+            // Palettes are expanded to RGB(A) by the decoder
+            png::ColorType::Grayscale | png::ColorType::Indexed => buffer
+                .iter()
+                .flat_map(|gray| vec![*gray, *gray, *gray, 255])
+                .collect(),
+        };
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels: pixels,
+        })
+    }
+
+    /// Save the image to `path` as a BMP or a PNG, depending on its extension
+    pub fn save<P>(&self, path: P) -> Result<(), AssetError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("png") => self.encode_png(BufWriter::new(File::create(path)?)),
+            Some("bmp") => {
+                let mut pixels = self.pixels.clone();
+                let surface = Surface::from_data(
+                    &mut pixels,
+                    self.width,
+                    self.height,
+                    self.width * 4,
+                    RGBA32,
+                )
+                .map_err(AssetError::Sdl)?;
+                surface.save_bmp(path).map_err(AssetError::Sdl)
+            }
+            _ => Err(AssetError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Encode the image as an 8 bit RGBA PNG
+    pub fn encode_png<W>(&self, writer: W) -> Result<(), AssetError>
+    where
+        W: Write,
+    {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(width: u32, height: u32, color: png::ColorType, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(color);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode_png_converts_to_rgba() {
+        let bytes = encode(2, 1, png::ColorType::GrayscaleAlpha, &[10, 20, 30, 40]);
+
+        let image = Image::decode_png(&bytes[..]).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![10, 10, 10, 20, 30, 30, 30, 40]);
+
+        let bytes = encode(1, 1, png::ColorType::Rgb, &[1, 2, 3]);
+        let image = Image::decode_png(&bytes[..]).unwrap();
+        assert_eq!(image.pixels, vec![1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn test_decode_invalid_png_is_an_error() {
+        let bytes = encode(1, 1, png::ColorType::Rgb, &[1, 2, 3]);
+
+        match Image::decode_png(&bytes[..bytes.len() / 2]) {
+            Err(AssetError::Png(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(Image::decode_png(&b"not a png"[..]).is_err());
+    }
+
+    #[test]
+    fn test_encoded_png_decodes_to_the_same_image() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![1, 2, 3, 4, 250, 251, 252, 253],
+        };
+        let mut bytes = vec![];
+
+        image.encode_png(&mut bytes).unwrap();
+
+        assert_eq!(Image::decode_png(&bytes[..]).unwrap(), image);
+        match image.save("frame.jpg") {
+            Err(AssetError::UnsupportedFormat(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
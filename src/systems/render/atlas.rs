@@ -0,0 +1,534 @@
+use serde_json;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use super::render_component::RenderComponentInner;
+use super::{Rect, TextureHandle};
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    /// The atlas packer rotated the frame of this name, which is not supported
+    RotatedFrame(String),
+    /// The frame of this name has no area
+    EmptyFrame(String),
+    /// The atlas packer trimmed the transparent border of the frame of this name, which is not
+    /// supported
+    TrimmedFrame(String),
+    /// The tag of this name is played in an unknown direction
+    UnknownDirection(String),
+    /// The tag of this name refers to frames the atlas does not have
+    TagOutOfRange(String),
+}
+
+impl From<io::Error> for AtlasError {
+    fn from(error: io::Error) -> Self {
+        AtlasError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for AtlasError {
+    fn from(error: serde_json::Error) -> Self {
+        AtlasError::Parse(error)
+    }
+}
+
+/// Frames of the same size laid out in a grid on a texture
+#[derive(Debug, Clone)]
+pub struct SpriteSheet {
+    texture: TextureHandle,
+    frame_size: (u32, u32),
+    columns: u32,
+    rows: u32,
+    /// Pixels between the edge of the texture and the first frame
+    margin: u32,
+    /// Pixels between two neighbouring frames
+    spacing: u32,
+}
+
+impl SpriteSheet {
+    /// Slice a texture of `texture_size` into frames of `frame_size`
+    pub fn new(
+        texture: TextureHandle,
+        texture_size: (u32, u32),
+        frame_size: (u32, u32),
+    ) -> SpriteSheet {
+        SpriteSheet::with_spacing(texture, texture_size, frame_size, 0, 0)
+    }
+
+    /// Slice a texture whose frames are separated by `spacing` pixels and surrounded by a
+    /// `margin`
+    pub fn with_spacing(
+        texture: TextureHandle,
+        texture_size: (u32, u32),
+        frame_size: (u32, u32),
+        margin: u32,
+        spacing: u32,
+    ) -> SpriteSheet {
+        assert!(
+            frame_size.0 > 0 && frame_size.1 > 0,
+            "Frames can not be empty"
+        );
+        let count = |texture: u32, frame: u32| {
+            (texture.saturating_sub(2 * margin) + spacing) / (frame + spacing)
+        };
+        SpriteSheet {
+            texture: texture,
+            frame_size: frame_size,
+            columns: count(texture_size.0, frame_size.0),
+            rows: count(texture_size.1, frame_size.1),
+            margin: margin,
+            spacing: spacing,
+        }
+    }
+
+    pub fn get_texture(&self) -> TextureHandle {
+        self.texture
+    }
+
+    pub fn len(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Source rectangle of the frame at `index`, counted row by row from the top left
+    pub fn frame(&self, index: usize) -> Option<Rect> {
+        if index >= self.len() {
+            return None;
+        }
+        let (column, row) = (index as u32 % self.columns, index as u32 / self.columns);
+        let (width, height) = self.frame_size;
+        Some(Rect::new(
+            (self.margin + column * (width + self.spacing)) as i32,
+            (self.margin + row * (height + self.spacing)) as i32,
+            width,
+            height,
+        ))
+    }
+
+    /// Draw the frame at `index` with `component`
+    /// Returns `false` if there is no such frame
+    pub fn apply(&self, index: usize, component: &mut RenderComponentInner) -> bool {
+        match self.frame(index) {
+            Some(source) => {
+                component.texture = Some(self.texture);
+                component.source = Some(source);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A named region of a `TextureAtlas`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasFrame {
+    pub name: String,
+    pub source: Rect,
+    /// How long the frame is shown when animated, if the atlas specifies a positive duration
+    pub duration: Option<Duration>,
+}
+
+/// Order the frames of a `FrameTag` are played in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagDirection {
+    Forward,
+    Reverse,
+    /// Forward, then backward
+    PingPong,
+    /// Backward, then forward
+    PingPongReverse,
+}
+
+impl TagDirection {
+    /// Parse the direction of an Aseprite tag
+    pub fn parse(direction: &str) -> Option<TagDirection> {
+        match direction {
+            "forward" => Some(TagDirection::Forward),
+            "reverse" => Some(TagDirection::Reverse),
+            "pingpong" => Some(TagDirection::PingPong),
+            "pingpong_reverse" => Some(TagDirection::PingPongReverse),
+            _ => None,
+        }
+    }
+}
+
+/// Named range of frames of a `TextureAtlas`, e.g. an animation exported by Aseprite
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameTag {
+    pub name: String,
+    /// Index of the first frame
+    pub from: usize,
+    /// Index of the last frame, inclusive
+    pub to: usize,
+    pub direction: TagDirection,
+}
+
+/// Named regions of a texture
+/// Can be loaded from the JSON files of TexturePacker and Aseprite, in both the hash and the
+/// array formats
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    texture: TextureHandle,
+    frames: Vec<AtlasFrame>,
+    names: HashMap<String, usize>,
+    tags: Vec<FrameTag>,
+}
+
+impl TextureAtlas {
+    pub fn new(texture: TextureHandle) -> TextureAtlas {
+        TextureAtlas {
+            texture: texture,
+            frames: vec![],
+            names: HashMap::new(),
+            tags: vec![],
+        }
+    }
+
+    /// Parse the JSON description of an atlas of `texture`
+    pub fn from_json(texture: TextureHandle, json: &str) -> Result<TextureAtlas, AtlasError> {
+        let file: json::AtlasFile = serde_json::from_str(json)?;
+        let frames: Vec<(String, json::FrameEntry)> = match file.frames {
+            json::Frames::Hash(frames) => frames
+                .into_iter()
+                .map(|(name, frame)| Ok((name, serde_json::from_value(frame)?)))
+                .collect::<Result<_, serde_json::Error>>()?,
+            json::Frames::Array(frames) => frames
+                .into_iter()
+                .map(|frame| (frame.filename.clone(), frame))
+                .collect(),
+        };
+        let mut atlas = TextureAtlas::new(texture);
+        for (name, frame) in frames {
+            if frame.rotated {
+                return Err(AtlasError::RotatedFrame(name));
+            }
+            if frame.is_trimmed() {
+                return Err(AtlasError::TrimmedFrame(name));
+            }
+            let rect = frame.frame;
+            if rect.w == 0 || rect.h == 0 {
+                return Err(AtlasError::EmptyFrame(name));
+            }
+            let source = Rect::new(rect.x, rect.y, rect.w, rect.h);
+            // Frames of no duration are shown for the default one, like frames without any
+            let duration = frame
+                .duration
+                .filter(|&duration| duration > 0)
+                .map(Duration::from_millis);
+            atlas.insert_frame(name, source, duration);
+        }
+        for tag in file.meta.frame_tags {
+            let direction = match TagDirection::parse(&tag.direction) {
+                Some(direction) => direction,
+                None => return Err(AtlasError::UnknownDirection(tag.name)),
+            };
+            if tag.from > tag.to || tag.to >= atlas.len() {
+                return Err(AtlasError::TagOutOfRange(tag.name));
+            }
+            atlas.tags.push(FrameTag {
+                name: tag.name,
+                from: tag.from,
+                to: tag.to,
+                direction: direction,
+            });
+        }
+        Ok(atlas)
+    }
+
+    /// Load the JSON description of an atlas of `texture` from a file
+    pub fn load_json<P>(texture: TextureHandle, path: P) -> Result<TextureAtlas, AtlasError>
+    where
+        P: AsRef<Path>,
+    {
+        let json = fs::read_to_string(path)?;
+        TextureAtlas::from_json(texture, &json)
+    }
+
+    pub fn get_texture(&self) -> TextureHandle {
+        self.texture
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Add a region named `name`, replacing the previous region of the same name
+    pub fn insert(&mut self, name: &str, source: Rect) {
+        self.insert_frame(name.to_string(), source, None);
+    }
+
+    /// The frames in the order they were listed in
+    pub fn get_frames(&self) -> &[AtlasFrame] {
+        &self.frames
+    }
+
+    pub fn get_frame(&self, index: usize) -> Option<&AtlasFrame> {
+        self.frames.get(index)
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.get(name).cloned()
+    }
+
+    /// Source rectangle of the region named `name`
+    pub fn get(&self, name: &str) -> Option<Rect> {
+        self.index_of(name).map(|index| self.frames[index].source)
+    }
+
+    pub fn get_tags(&self) -> &[FrameTag] {
+        &self.tags
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&FrameTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Draw the region named `name` with `component`
+    /// Returns `false` if there is no such region
+    pub fn apply(&self, name: &str, component: &mut RenderComponentInner) -> bool {
+        match self.get(name) {
+            Some(source) => {
+                component.texture = Some(self.texture);
+                component.source = Some(source);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert_frame(&mut self, name: String, source: Rect, duration: Option<Duration>) {
+        let frame = AtlasFrame {
+            name: name.clone(),
+            source: source,
+            duration: duration,
+        };
+        match self.names.get(&name) {
+            Some(index) => self.frames[*index] = frame,
+            None => {
+                self.names.insert(name, self.frames.len());
+                self.frames.push(frame);
+            }
+        }
+    }
+}
+
+/// Layout of the atlas files
+mod json {
+    use serde_json::{Map, Value};
+
+    #[derive(Deserialize)]
+    pub struct AtlasFile {
+        pub frames: Frames,
+        #[serde(default)]
+        pub meta: Meta,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum Frames {
+        /// Frames by name, in the order of the file
+        Hash(Map<String, Value>),
+        Array(Vec<FrameEntry>),
+    }
+
+    #[derive(Deserialize)]
+    pub struct FrameEntry {
+        #[serde(default)]
+        pub filename: String,
+        pub frame: FrameRect,
+        #[serde(default)]
+        pub rotated: bool,
+        #[serde(default)]
+        pub trimmed: bool,
+        #[serde(rename = "spriteSourceSize")]
+        pub sprite_source_size: Option<FrameRect>,
+        #[serde(rename = "sourceSize")]
+        pub source_size: Option<Size>,
+        pub duration: Option<u64>,
+    }
+
+    impl FrameEntry {
+        /// Whether the frame is only a part of the original sprite
+        pub fn is_trimmed(&self) -> bool {
+            match (self.sprite_source_size.as_ref(), self.source_size.as_ref()) {
+                (Some(sprite), Some(source)) => {
+                    (sprite.x, sprite.y, sprite.w, sprite.h) != (0, 0, source.w, source.h)
+                }
+                _ => self.trimmed,
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct FrameRect {
+        pub x: i32,
+        pub y: i32,
+        pub w: u32,
+        pub h: u32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Size {
+        pub w: u32,
+        pub h: u32,
+    }
+
+    #[derive(Deserialize, Default)]
+    pub struct Meta {
+        #[serde(rename = "frameTags", default)]
+        pub frame_tags: Vec<Tag>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Tag {
+        pub name: String,
+        pub from: usize,
+        pub to: usize,
+        #[serde(default = "forward")]
+        pub direction: String,
+    }
+
+    fn forward() -> String {
+        "forward".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::common::arena::Arena;
+    use super::*;
+
+    fn texture() -> TextureHandle {
+        TextureHandle(Arena::new().insert(()))
+    }
+
+    #[test]
+    fn test_sprite_sheet_frames() {
+        let sheet = SpriteSheet::with_spacing(texture(), (70, 40), (16, 16), 2, 2);
+
+        assert_eq!(sheet.len(), 6);
+        assert_eq!(sheet.frame(0), Some(Rect::new(2, 2, 16, 16)));
+        assert_eq!(sheet.frame(5), Some(Rect::new(38, 20, 16, 16)));
+        assert_eq!(sheet.frame(6), None);
+    }
+
+    #[test]
+    fn test_load_texture_packer_hash() {
+        let json = r#"{
+            "frames": {
+                "walk_2.png": {"frame": {"x": 32, "y": 0, "w": 16, "h": 24}, "rotated": false},
+                "walk_1.png": {"frame": {"x": 0, "y": 0, "w": 16, "h": 24}, "rotated": false}
+            },
+            "meta": {"image": "player.png"}
+        }"#;
+
+        let atlas = TextureAtlas::from_json(texture(), json).unwrap();
+
+        assert_eq!(atlas.len(), 2);
+        assert_eq!(atlas.get("walk_1.png"), Some(Rect::new(0, 0, 16, 24)));
+        // The order of the file is kept
+        assert_eq!(atlas.get_frame(0).unwrap().name, "walk_2.png");
+
+        let mut component = RenderComponentInner::new();
+        assert!(atlas.apply("walk_2.png", &mut component));
+        assert_eq!(component.source, Some(Rect::new(32, 0, 16, 24)));
+        assert!(!atlas.apply("run_1.png", &mut component));
+    }
+
+    #[test]
+    fn test_load_aseprite_array_with_tags() {
+        let json = r#"{
+            "frames": [
+                {"filename": "idle 0", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 100},
+                {"filename": "idle 1", "frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 150}
+            ],
+            "meta": {"frameTags": [{"name": "idle", "from": 0, "to": 1, "direction": "pingpong"}]}
+        }"#;
+
+        let atlas = TextureAtlas::from_json(texture(), json).unwrap();
+
+        assert_eq!(atlas.index_of("idle 1"), Some(1));
+        assert_eq!(
+            atlas.get_frame(1).unwrap().duration,
+            Some(Duration::from_millis(150))
+        );
+        let tag = atlas.get_tag("idle").unwrap();
+        assert_eq!((tag.from, tag.to), (0, 1));
+        assert_eq!(tag.direction, TagDirection::PingPong);
+    }
+
+    #[test]
+    fn test_rotated_frames_are_rejected() {
+        let json =
+            r#"{"frames": {"a": {"frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "rotated": true}}}"#;
+
+        match TextureAtlas::from_json(texture(), json) {
+            Err(AtlasError::RotatedFrame(name)) => assert_eq!(name, "a"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(TextureAtlas::from_json(texture(), "{").is_err());
+    }
+
+    #[test]
+    fn test_trimmed_frames_are_rejected() {
+        let json = r#"{"frames": {"a": {
+            "frame": {"x": 0, "y": 0, "w": 6, "h": 8},
+            "trimmed": true,
+            "spriteSourceSize": {"x": 1, "y": 0, "w": 6, "h": 8},
+            "sourceSize": {"w": 8, "h": 8}
+        }}}"#;
+
+        match TextureAtlas::from_json(texture(), json) {
+            Err(AtlasError::TrimmedFrame(name)) => assert_eq!(name, "a"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        // Aseprite lists the full size of untrimmed frames
+        let json = r#"{"frames": {"a": {
+            "frame": {"x": 0, "y": 0, "w": 8, "h": 8},
+            "trimmed": false,
+            "spriteSourceSize": {"x": 0, "y": 0, "w": 8, "h": 8},
+            "sourceSize": {"w": 8, "h": 8}
+        }}}"#;
+        assert!(TextureAtlas::from_json(texture(), json).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_tags_are_rejected() {
+        let atlas = |tag: &str| {
+            TextureAtlas::from_json(
+                texture(),
+                &format!(
+                    r#"{{
+                        "frames": [{{"filename": "a", "frame": {{"x": 0, "y": 0, "w": 8, "h": 8}}}}],
+                        "meta": {{"frameTags": [{}]}}
+                    }}"#,
+                    tag
+                ),
+            )
+        };
+
+        match atlas(r#"{"name": "idle", "from": 0, "to": 0, "direction": "sideways"}"#) {
+            Err(AtlasError::UnknownDirection(name)) => assert_eq!(name, "idle"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match atlas(r#"{"name": "run", "from": 0, "to": 1}"#) {
+            Err(AtlasError::TagOutOfRange(name)) => assert_eq!(name, "run"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let atlas = atlas(r#"{"name": "idle", "from": 0, "to": 0}"#).unwrap();
+        assert_eq!(atlas.get_tags()[0].direction, TagDirection::Forward);
+    }
+}
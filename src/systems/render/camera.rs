@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use super::super::super::common::vector2::Vector2;
+use super::Rect;
+
+/// View into the world, transforms world-space render components to the screen
+/// The default camera of a viewport maps world coordinates to the same screen coordinates
+#[derive(Debug, Clone)]
+pub struct Camera2D {
+    /// World point shown at the center of the viewport
+    pub position: Vector2,
+    /// Scale of the world on the screen, 2 shows everything twice as big
+    pub zoom: f32,
+    /// Rotation of the camera in degrees, clockwise
+    pub rotation: f32,
+    /// Area of the screen the world is drawn into
+    pub viewport: Rect,
+    /// Half size of the area around `position` the followed target moves freely in
+    pub deadzone: Vector2,
+    /// How fast the camera catches up with the followed target, 0 snaps to it
+    /// After `1 / follow_speed` seconds about 63% of the distance is covered
+    pub follow_speed: f32,
+}
+
+impl Camera2D {
+    pub fn new(viewport: Rect) -> Camera2D {
+        let center = viewport.center();
+        Camera2D {
+            position: Vector2::new(center.x() as f32, center.y() as f32),
+            zoom: 1.,
+            rotation: 0.,
+            viewport: viewport,
+            deadzone: Vector2::new(0., 0.),
+            follow_speed: 0.,
+        }
+    }
+
+    /// Transform a point of the world to the screen
+    pub fn world_to_screen(&self, point: &Vector2) -> Vector2 {
+        let offset = point.sub(&self.position).rotate(-self.rotation);
+        self.viewport_center().add(&(self.zoom * offset))
+    }
+
+    /// Transform a point of the screen to the world, e.g. the position of the mouse
+    pub fn screen_to_world(&self, point: &Vector2) -> Vector2 {
+        let offset = (1. / self.zoom) * point.sub(&self.viewport_center());
+        self.position.add(&offset.rotate(self.rotation))
+    }
+
+    /// Transform a rectangle of the world to the screen
+    /// The rectangle is scaled around its center, its rotation is `screen_angle`
+    pub fn world_to_screen_rect(&self, rect: &Rect) -> Rect {
+        let center = rect.center();
+        let center = self.world_to_screen(&Vector2::new(center.x() as f32, center.y() as f32));
+        let width = (rect.width() as f32 * self.zoom).round() as u32;
+        let height = (rect.height() as f32 * self.zoom).round() as u32;
+        let mut result = Rect::new(0, 0, width.max(1), height.max(1));
+        result.center_on((center.x.round() as i32, center.y.round() as i32));
+        result
+    }
+
+    /// The smallest rectangle of the world containing everything the viewport shows
+    pub fn visible_area(&self) -> Rect {
+        let viewport = &self.viewport;
+        let corners: Vec<Vector2> = [
+            (viewport.left(), viewport.top()),
+            (viewport.right(), viewport.top()),
+            (viewport.right(), viewport.bottom()),
+            (viewport.left(), viewport.bottom()),
+        ]
+        .iter()
+        .map(|&(x, y)| self.screen_to_world(&Vector2::new(x as f32, y as f32)))
+        .collect();
+        let left = corners
+            .iter()
+            .map(|corner| corner.x)
+            .fold(f32::MAX, f32::min);
+        let top = corners
+            .iter()
+            .map(|corner| corner.y)
+            .fold(f32::MAX, f32::min);
+        let right = corners
+            .iter()
+            .map(|corner| corner.x)
+            .fold(f32::MIN, f32::max);
+        let bottom = corners
+            .iter()
+            .map(|corner| corner.y)
+            .fold(f32::MIN, f32::max);
+        Rect::new(
+            left.floor() as i32,
+            top.floor() as i32,
+            (right.ceil() - left.floor()).max(1.) as u32,
+            (bottom.ceil() - top.floor()).max(1.) as u32,
+        )
+    }
+
+    /// Rotation of world-space sprites on the screen in degrees, clockwise
+    pub fn screen_angle(&self) -> f64 {
+        -f64::from(self.rotation)
+    }
+
+    /// Move the camera towards `target`, keeping it inside the deadzone
+    /// Call it once per fixed update with the update's delta time
+    pub fn follow(&mut self, target: &Vector2, delta_time: &Duration) {
+        let mut desired = self.position.clone();
+        for axis in 0..2 {
+            let offset = target.get(axis) - self.position.get(axis);
+            let deadzone = self.deadzone.get(axis);
+            if offset > deadzone {
+                *desired.get_mut(axis) = target.get(axis) - deadzone;
+            } else if offset < -deadzone {
+                *desired.get_mut(axis) = target.get(axis) + deadzone;
+            }
+        }
+        let t = if self.follow_speed > 0. {
+            1. - (-self.follow_speed * delta_time.as_secs_f32()).exp()
+        } else {
+            1.
+        };
+        let step = t * desired.sub(&self.position);
+        self.position.add_mut(&step);
+    }
+
+    fn viewport_center(&self) -> Vector2 {
+        let center = self.viewport.center();
+        Vector2::new(center.x() as f32, center.y() as f32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(actual: &Vector2, expected: &Vector2) {
+        assert!(
+            actual.sub(expected).length() < 1e-3,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_default_camera_maps_world_to_the_same_screen_point() {
+        let camera = Camera2D::new(Rect::new(0, 0, 800, 600));
+        let point = Vector2::new(12., 34.);
+
+        assert_close(&camera.world_to_screen(&point), &point);
+        assert_eq!(
+            camera.world_to_screen_rect(&Rect::new(10, 20, 30, 40)),
+            Rect::new(10, 20, 30, 40)
+        );
+    }
+
+    #[test]
+    fn test_zoom_and_rotation_round_trip() {
+        let mut camera = Camera2D::new(Rect::new(0, 0, 800, 600));
+        camera.position = Vector2::new(1000., 1000.);
+        camera.zoom = 2.;
+        camera.rotation = 90.;
+
+        // Right of the camera is up on the screen when rotated clockwise by 90 degrees
+        let screen = camera.world_to_screen(&Vector2::new(1010., 1000.));
+        assert_close(&screen, &Vector2::new(400., 280.));
+        assert_close(
+            &camera.screen_to_world(&screen),
+            &Vector2::new(1010., 1000.),
+        );
+        assert_eq!(
+            camera.world_to_screen_rect(&Rect::new(995, 995, 10, 10)),
+            Rect::new(390, 290, 20, 20)
+        );
+    }
+
+    #[test]
+    fn test_visible_area_contains_the_rotated_viewport() {
+        let mut camera = Camera2D::new(Rect::new(0, 0, 200, 100));
+        camera.zoom = 2.;
+
+        assert_eq!(camera.visible_area(), Rect::new(50, 25, 100, 50));
+
+        camera.rotation = 90.;
+        assert_eq!(camera.visible_area(), Rect::new(75, 0, 50, 100));
+    }
+
+    #[test]
+    fn test_follow_respects_the_deadzone() {
+        let mut camera = Camera2D::new(Rect::new(0, 0, 100, 100));
+        camera.deadzone = Vector2::new(10., 10.);
+        let frame = Duration::from_millis(16);
+
+        camera.follow(&Vector2::new(55., 45.), &frame);
+        assert_close(&camera.position, &Vector2::new(50., 50.));
+
+        camera.follow(&Vector2::new(80., 50.), &frame);
+        assert_close(&camera.position, &Vector2::new(70., 50.));
+    }
+
+    #[test]
+    fn test_smooth_follow_approaches_the_target() {
+        let mut camera = Camera2D::new(Rect::new(0, 0, 100, 100));
+        camera.follow_speed = 1.;
+
+        camera.follow(&Vector2::new(150., 50.), &Duration::from_secs(1));
+
+        let expected = 50. + 100. * (1. - (-1f32).exp());
+        assert_close(&camera.position, &Vector2::new(expected, 50.));
+    }
+}
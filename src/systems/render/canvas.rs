@@ -0,0 +1,34 @@
+use sdl2::render;
+use sdl2::surface::Surface;
+use sdl2::video::Window;
+
+/// Where a `RenderSystem` draws, a window or an offscreen surface
+/// Textures are created by the canvas, see `RenderSystem::create_texture`
+pub enum Canvas {
+    Window(render::Canvas<Window>),
+    /// Drawn in software into memory, needs neither a display nor SDL's video subsystem
+    Surface(render::Canvas<Surface<'static>>),
+}
+
+/// Evaluate `$body` with `$inner` bound to the `sdl2` canvas of `$canvas`, whatever its kind
+macro_rules! with_canvas {
+    ($canvas:expr, ref mut $inner:ident => $body:expr) => {
+        match $canvas {
+            Canvas::Window(ref mut $inner) => $body,
+            Canvas::Surface(ref mut $inner) => $body,
+        }
+    };
+    ($canvas:expr, ref $inner:ident => $body:expr) => {
+        match $canvas {
+            Canvas::Window(ref $inner) => $body,
+            Canvas::Surface(ref $inner) => $body,
+        }
+    };
+}
+
+impl Canvas {
+    /// Size of the window or surface in pixels
+    pub fn output_size(&self) -> Result<(u32, u32), String> {
+        with_canvas!(*self, ref canvas => canvas.output_size())
+    }
+}
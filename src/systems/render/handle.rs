@@ -0,0 +1,15 @@
+use super::super::super::common::arena::Index;
+
+/// Copyable reference to a component of the `RenderSystem`
+/// Handles of deleted components are detected, `RenderSystem::try_get` returns `None` for them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ComponentHandle(pub(crate) Index);
+
+/// Reference to a texture owned by the `RenderSystem`
+/// Handles of deleted textures are detected, components referencing them are not drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureHandle(pub(crate) Index);
+
+/// Reference to a render target of the `RenderSystem`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RenderTargetHandle(pub(crate) Index);
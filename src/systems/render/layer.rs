@@ -0,0 +1,16 @@
+/// Group of render components drawn together, lower layers are drawn first
+/// Use the named layers, or any order between them for custom ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Layer(pub i32);
+
+impl Layer {
+    pub const BACKGROUND: Layer = Layer(-100);
+    pub const WORLD: Layer = Layer(0);
+    pub const UI: Layer = Layer(100);
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Layer::WORLD
+    }
+}
@@ -1,14 +1,33 @@
 use sdl2::{pixels, rect, render, video, Sdl};
-use std::rc::Rc;
 
 pub type Window = video::Window;
-pub type Canvas = render::Canvas<Window>;
-pub type TextureCreator = render::TextureCreator<video::WindowContext>;
-pub type Texture<'a> = render::Texture<'a>;
-pub type TexturePtr<'a> = Rc<Box<Texture<'a>>>;
+pub type Texture = render::Texture;
 pub type Rect = rect::Rect;
+pub type Point = rect::Point;
+pub type BlendMode = render::BlendMode;
 pub type Color = pixels::Color;
 
+mod assets;
+#[macro_use]
+mod canvas;
+mod atlas;
+mod camera;
+mod handle;
+mod layer;
+mod primitives;
 pub mod render_component;
 mod render_system;
+mod target;
+mod text;
+mod tilemap;
+pub use self::assets::{AssetError, Image, TextureAsset};
+pub use self::atlas::{AtlasError, AtlasFrame, FrameTag, SpriteSheet, TagDirection, TextureAtlas};
+pub use self::camera::Camera2D;
+pub use self::canvas::Canvas;
+pub use self::handle::{ComponentHandle, RenderTargetHandle, TextureHandle};
+pub use self::layer::Layer;
+pub use self::primitives::{Primitives, Shape, Space};
 pub use self::render_system::*;
+pub use self::target::RenderTarget;
+pub use self::text::{BitmapFont, FontError, Glyph, PlacedGlyph, TextAlign, TextLayout, TextStyle};
+pub use self::tilemap::{PlacedTile, Tile, TileFrame, TileLayer, Tilemap, Tileset, CHUNK_SIZE};
@@ -0,0 +1,323 @@
+use sdl2::render;
+use std::f32::consts::PI;
+
+use super::super::super::common::aabb::AABB;
+use super::super::super::common::circle::Circle;
+use super::super::super::common::obb::OBB2D;
+use super::super::super::common::vector2::Vector2;
+use super::{BlendMode, Camera2D, Canvas, Color, Point};
+
+/// Coordinates shapes are given in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Space {
+    /// Transformed by the camera and clipped to its viewport
+    World,
+    Screen,
+}
+
+/// Shapes the `Primitives` can outline and fill
+pub trait Shape {
+    /// Corners of the shape in order, curves are approximated by `segments` lines
+    fn polygon(&self, segments: usize) -> Vec<Vector2>;
+}
+
+impl Shape for AABB {
+    fn polygon(&self, _segments: usize) -> Vec<Vector2> {
+        let (center, radius) = (self.get_center(), self.get_radius());
+        vec![
+            Vector2::new(center.x - radius.x, center.y - radius.y),
+            Vector2::new(center.x + radius.x, center.y - radius.y),
+            Vector2::new(center.x + radius.x, center.y + radius.y),
+            Vector2::new(center.x - radius.x, center.y + radius.y),
+        ]
+    }
+}
+
+impl Shape for Circle {
+    fn polygon(&self, segments: usize) -> Vec<Vector2> {
+        let segments = segments.max(3);
+        (0..segments)
+            .map(|i| {
+                let angle = 2. * PI * i as f32 / segments as f32;
+                let offset = Vector2::new(angle.cos(), angle.sin());
+                self.get_center().add(&(self.get_radius() * offset))
+            })
+            .collect()
+    }
+}
+
+impl Shape for OBB2D {
+    fn polygon(&self, _segments: usize) -> Vec<Vector2> {
+        let local = self.get_local();
+        let extents = self.get_extents();
+        let x = extents.x * local[0].clone();
+        let y = extents.y * local[1].clone();
+        let center = self.get_center();
+        vec![
+            center.sub(&x).sub(&y),
+            center.add(&x).sub(&y),
+            center.add(&x).add(&y),
+            center.sub(&x).add(&y),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Lines,
+    /// Lines connecting the last point with the first
+    Loop,
+    Filled,
+}
+
+#[derive(Debug, Clone)]
+struct Primitive {
+    points: Vec<Vector2>,
+    style: Style,
+    color: Color,
+    space: Space,
+}
+
+/// Shapes drawn on top of the components by the next `RenderSystem::render`
+/// The queue is emptied after every frame, queue shapes in `Game::render` to draw them on every
+/// frame
+#[derive(Debug, Clone)]
+pub struct Primitives {
+    queue: Vec<Primitive>,
+    space: Space,
+    /// Number of lines approximating circles
+    pub circle_segments: usize,
+}
+
+impl Primitives {
+    pub fn new() -> Primitives {
+        Primitives {
+            queue: vec![],
+            space: Space::World,
+            circle_segments: 32,
+        }
+    }
+
+    pub fn get_space(&self) -> Space {
+        self.space
+    }
+
+    /// Shapes queued from now on are in `space`
+    /// Returns the previous space
+    pub fn set_space(&mut self, space: Space) -> Space {
+        ::std::mem::replace(&mut self.space, space)
+    }
+
+    pub fn line(&mut self, from: &Vector2, to: &Vector2, color: Color) {
+        self.push(vec![from.clone(), to.clone()], Style::Lines, color);
+    }
+
+    /// Lines connecting `points` in order
+    pub fn polyline(&mut self, points: &[Vector2], color: Color) {
+        self.push(points.to_vec(), Style::Lines, color);
+    }
+
+    /// Outline of the polygon of `points`
+    pub fn polygon(&mut self, points: &[Vector2], color: Color) {
+        self.push(points.to_vec(), Style::Loop, color);
+    }
+
+    /// Fill the convex polygon of `points`
+    pub fn fill_polygon(&mut self, points: &[Vector2], color: Color) {
+        self.push(points.to_vec(), Style::Filled, color);
+    }
+
+    pub fn outline<S>(&mut self, shape: &S, color: Color)
+    where
+        S: Shape,
+    {
+        let points = shape.polygon(self.circle_segments);
+        self.push(points, Style::Loop, color);
+    }
+
+    pub fn fill<S>(&mut self, shape: &S, color: Color)
+    where
+        S: Shape,
+    {
+        let points = shape.polygon(self.circle_segments);
+        self.push(points, Style::Filled, color);
+    }
+
+    /// The points and colors of the queued shapes, in the order they are drawn
+    pub fn iter(&self) -> impl Iterator<Item = (&[Vector2], Color)> {
+        self.queue
+            .iter()
+            .map(|primitive| (&primitive.points[..], primitive.color))
+    }
+
+    /// Number of shapes queued
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Draw and dequeue every shape
+    pub(crate) fn flush(&mut self, canvas: &mut Canvas, camera: &Camera2D) {
+        with_canvas!(*canvas, ref mut canvas => self.flush_into(canvas, camera))
+    }
+
+    fn flush_into<T>(&mut self, canvas: &mut render::Canvas<T>, camera: &Camera2D)
+    where
+        T: render::RenderTarget,
+    {
+        for primitive in self.queue.drain(..) {
+            let space = primitive.space;
+            let mut points: Vec<Point> = primitive
+                .points
+                .iter()
+                .map(|point| match space {
+                    Space::World => to_point(&camera.world_to_screen(point)),
+                    Space::Screen => to_point(point),
+                })
+                .collect();
+            canvas.set_clip_rect(match space {
+                Space::World => Some(camera.viewport),
+                Space::Screen => None,
+            });
+            canvas.set_draw_color(primitive.color);
+            canvas.set_blend_mode(if primitive.color.a < 255 {
+                BlendMode::Blend
+            } else {
+                BlendMode::None
+            });
+            let result = match primitive.style {
+                Style::Lines => canvas.draw_lines(&points[..]),
+                Style::Loop => {
+                    if let Some(first) = points.first().cloned() {
+                        points.push(first);
+                    }
+                    canvas.draw_lines(&points[..])
+                }
+                Style::Filled => scanlines(&points)
+                    .into_iter()
+                    .try_for_each(|(from, to)| canvas.draw_line(from, to)),
+            };
+            result.unwrap();
+        }
+        canvas.set_clip_rect(None);
+        canvas.set_blend_mode(BlendMode::None);
+    }
+
+    fn push(&mut self, points: Vec<Vector2>, style: Style, color: Color) {
+        self.queue.push(Primitive {
+            points: points,
+            style: style,
+            color: color,
+            space: self.space,
+        });
+    }
+}
+
+impl Default for Primitives {
+    fn default() -> Self {
+        Primitives::new()
+    }
+}
+
+fn to_point(point: &Vector2) -> Point {
+    Point::new(point.x.round() as i32, point.y.round() as i32)
+}
+
+/// Horizontal lines filling the convex polygon of `points`, from the top row to the bottom
+fn scanlines(points: &[Point]) -> Vec<(Point, Point)> {
+    let top = points.iter().map(|point| point.y()).min().unwrap_or(0);
+    let bottom = points.iter().map(|point| point.y()).max().unwrap_or(-1);
+    let mut lines = vec![];
+    for y in top..=bottom {
+        let mut left = i32::MAX;
+        let mut right = i32::MIN;
+        for (i, from) in points.iter().enumerate() {
+            let to = points[(i + 1) % points.len()];
+            if (from.y() > y) == (to.y() > y) && from.y() != y && to.y() != y {
+                continue;
+            }
+            let xs = if from.y() == to.y() {
+                (from.x(), to.x())
+            } else {
+                let t = (y - from.y()) as f32 / (to.y() - from.y()) as f32;
+                let x = (from.x() as f32 + t * (to.x() - from.x()) as f32).round() as i32;
+                (x, x)
+            };
+            left = left.min(xs.0.min(xs.1));
+            right = right.max(xs.0.max(xs.1));
+        }
+        if left <= right {
+            lines.push((Point::new(left, y), Point::new(right, y)));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shapes_are_queued_in_the_current_space() {
+        let mut primitives = Primitives::new();
+        primitives.line(
+            &Vector2::new(0., 0.),
+            &Vector2::new(1., 1.),
+            Color::RGB(255, 0, 0),
+        );
+
+        assert_eq!(primitives.set_space(Space::Screen), Space::World);
+        primitives.fill(
+            &Circle::new(Vector2::new(0., 0.), 2.),
+            Color::RGB(0, 255, 0),
+        );
+
+        assert_eq!(primitives.len(), 2);
+        assert_eq!(primitives.queue[0].space, Space::World);
+        assert_eq!(primitives.queue[1].space, Space::Screen);
+        assert_eq!(primitives.queue[1].points.len(), 32);
+        primitives.clear();
+        assert!(primitives.is_empty());
+    }
+
+    #[test]
+    fn test_shape_polygons() {
+        let aabb = AABB::new(Vector2::new(10., 10.), 4., 2.);
+        assert_eq!(
+            aabb.polygon(0),
+            vec![
+                Vector2::new(8., 9.),
+                Vector2::new(12., 9.),
+                Vector2::new(12., 11.),
+                Vector2::new(8., 11.),
+            ]
+        );
+
+        // Rotated by 90 degrees, the box's x axis points down
+        let obb = OBB2D::new(
+            Vector2::new(0., 0.),
+            [Vector2::new(0., 1.), Vector2::new(-1., 0.)],
+            Vector2::new(2., 1.),
+        );
+        assert_eq!(obb.polygon(0)[0], Vector2::new(1., -2.));
+    }
+
+    #[test]
+    fn test_scanlines_fill_a_triangle() {
+        let triangle = [Point::new(0, 0), Point::new(4, 4), Point::new(0, 4)];
+
+        let lines = scanlines(&triangle);
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], (Point::new(0, 0), Point::new(0, 0)));
+        assert_eq!(lines[2], (Point::new(0, 2), Point::new(2, 2)));
+        assert_eq!(lines[4], (Point::new(0, 4), Point::new(4, 4)));
+    }
+}
@@ -1,19 +1,206 @@
 use super::*;
-use std::ptr;
 
 /// Represents a renderable object
-pub struct RenderComponentInner<'a> {
-    pub texture: *const Texture<'a>,
+#[derive(Debug, Clone)]
+pub struct RenderComponentInner {
+    /// Texture drawn at `position`, nothing is drawn if it is `None` or deleted
+    pub texture: Option<TextureHandle>,
+    /// Part of the texture drawn, the whole texture if `None`
+    pub source: Option<Rect>,
+    /// Text drawn instead of the texture, scaled to `position`, see `set_text`
+    /// Text is not flipped
+    pub text: Option<TextLayout>,
+    /// Tiles drawn instead of the texture from the top left corner of `position`, see
+    /// `set_tilemap`
+    /// Tilemaps are not rotated or flipped as a whole
+    pub tilemap: Option<Tilemap>,
     pub position: Rect,
-    pub id: usize,
+    /// Position at the start of the last fixed update
+    pub previous_position: Rect,
+    /// Draw the component between `previous_position` and `position` based on how far the
+    /// engine is into the next fixed update, smoothing out movement on high refresh rates
+    pub interpolate: bool,
+    /// `position` is on the screen instead of in the world, the camera does not affect it
+    /// Use it for UI elements
+    pub screen_space: bool,
+    /// Rotation in degrees, clockwise
+    pub angle: f64,
+    /// Point of `position` the component is rotated around, its center if `None`
+    pub pivot: Option<Point>,
+    /// Mirror the texture horizontally
+    pub flip_h: bool,
+    /// Mirror the texture vertically
+    pub flip_v: bool,
+    /// Color multiplied with the texture, white leaves it unchanged
+    pub tint: Color,
+    /// Opacity, 0 is invisible and 255 is opaque
+    pub alpha: u8,
+    /// How the texture is combined with what is drawn below it
+    pub blend_mode: BlendMode,
+    /// Layer the component is drawn in
+    pub layer: Layer,
+    /// Order of the component inside its layer, higher values are drawn on top
+    /// Components with the same `z` are drawn in the order they were created
+    pub z: i32,
+    /// Order the component was created in
+    pub(crate) sequence: usize,
+    /// Scope the component was created in, see `RenderSystem::set_scope`
+    pub(crate) scope: usize,
 }
 
-impl<'a> RenderComponentInner<'a> {
-    pub fn new(id: usize) -> RenderComponentInner<'a> {
+impl RenderComponentInner {
+    pub fn new() -> RenderComponentInner {
         RenderComponentInner {
-            texture: ptr::null(),
+            texture: None,
+            source: None,
+            text: None,
+            tilemap: None,
             position: Rect::new(0, 0, 0, 0),
-            id: id,
+            previous_position: Rect::new(0, 0, 0, 0),
+            interpolate: false,
+            screen_space: false,
+            angle: 0.,
+            pivot: None,
+            flip_h: false,
+            flip_v: false,
+            tint: Color::RGB(255, 255, 255),
+            alpha: 255,
+            blend_mode: BlendMode::Blend,
+            layer: Layer::WORLD,
+            z: 0,
+            sequence: 0,
+            scope: GLOBAL_SCOPE,
         }
     }
+
+    /// Move the component without interpolating from its previous position
+    pub fn teleport(&mut self, position: Rect) {
+        self.position = position;
+        self.previous_position = position;
+    }
+
+    /// Draw `text` in `font` instead of the texture, tinted with the color of `style`
+    /// `position` keeps its top left corner and is resized to the size of the text
+    pub fn set_text(&mut self, font: &BitmapFont, text: &str, style: &TextStyle) {
+        let layout = font.layout(text, style);
+        for position in [&mut self.position, &mut self.previous_position].iter_mut() {
+            position.set_width(layout.width);
+            position.set_height(layout.height);
+        }
+        self.tint = style.color;
+        self.text = Some(layout);
+    }
+
+    /// Draw `tilemap` instead of the texture
+    /// `position` keeps its top left corner and is resized to the size of the tilemap
+    pub fn set_tilemap(&mut self, tilemap: Tilemap) {
+        let (width, height) = tilemap.pixel_size();
+        for position in [&mut self.position, &mut self.previous_position].iter_mut() {
+            position.set_width(width);
+            position.set_height(height);
+        }
+        self.tilemap = Some(tilemap);
+    }
+
+    /// Calculate the position between `previous_position` and `position`
+    /// `alpha` of 0 yields the previous position, 1 the current one
+    pub fn interpolated_position(&self, alpha: f32) -> Rect {
+        let from = &self.previous_position;
+        let to = &self.position;
+        Rect::new(
+            lerp(from.x() as f32, to.x() as f32, alpha).round() as i32,
+            lerp(from.y() as f32, to.y() as f32, alpha).round() as i32,
+            lerp(from.width() as f32, to.width() as f32, alpha).round() as u32,
+            lerp(from.height() as f32, to.height() as f32, alpha).round() as u32,
+        )
+    }
+
+    /// Components are drawn in ascending order of this key
+    pub fn draw_order(&self) -> (Layer, i32, usize) {
+        (self.layer, self.z, self.sequence)
+    }
+
+    /// The position the component is drawn at
+    pub fn render_position(&self, alpha: f32) -> Rect {
+        if self.interpolate {
+            self.interpolated_position(alpha)
+        } else {
+            self.position
+        }
+    }
+}
+
+impl Default for RenderComponentInner {
+    fn default() -> Self {
+        RenderComponentInner::new()
+    }
+}
+
+fn lerp(from: f32, to: f32, alpha: f32) -> f32 {
+    from + (to - from) * alpha
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interpolated_position() {
+        let mut component = RenderComponentInner::new();
+        component.previous_position = Rect::new(0, 10, 10, 10);
+        component.position = Rect::new(10, -10, 20, 10);
+
+        assert_eq!(
+            component.interpolated_position(0.),
+            Rect::new(0, 10, 10, 10)
+        );
+        assert_eq!(
+            component.interpolated_position(0.5),
+            Rect::new(5, 0, 15, 10)
+        );
+        assert_eq!(
+            component.interpolated_position(1.),
+            Rect::new(10, -10, 20, 10)
+        );
+    }
+
+    #[test]
+    fn test_teleport_does_not_interpolate() {
+        let mut component = RenderComponentInner::new();
+        component.interpolate = true;
+        component.teleport(Rect::new(100, 100, 10, 10));
+
+        assert_eq!(component.render_position(0.5), Rect::new(100, 100, 10, 10));
+    }
+
+    #[test]
+    fn test_draw_order() {
+        let component = |layer: Layer, z: i32, sequence: usize| {
+            let mut component = RenderComponentInner::new();
+            component.layer = layer;
+            component.z = z;
+            component.sequence = sequence;
+            component
+        };
+        let mut components = [
+            component(Layer::UI, -10, 0),
+            component(Layer::WORLD, 1, 1),
+            component(Layer::WORLD, 0, 3),
+            component(Layer::WORLD, 0, 2),
+            component(Layer::BACKGROUND, 5, 4),
+        ];
+
+        components.sort_by_key(|component| component.draw_order());
+
+        let sequences: Vec<usize> = components.iter().map(|c| c.sequence).collect();
+        assert_eq!(sequences, vec![4, 2, 3, 1, 0]);
+    }
+
+    #[test]
+    fn test_render_position_ignores_alpha_without_interpolation() {
+        let mut component = RenderComponentInner::new();
+        component.position = Rect::new(10, 10, 10, 10);
+
+        assert_eq!(component.render_position(0.), Rect::new(10, 10, 10, 10));
+    }
 }
@@ -1,44 +1,141 @@
 pub use super::super::super::components::render::RenderComponent;
 pub use super::render_component::RenderComponentInner;
-use std::collections::BTreeMap;
+use sdl2::render;
+use sdl2::surface::Surface;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::super::super::common::arena::Arena;
+use super::assets::RGBA32;
 
 use super::*;
 
 pub const WINDOW_SIZE: (u32, u32) = (800, 600);
+pub const WINDOW_TITLE: &str = "Blight Engine";
+/// Scope of the components not owned by a scene
+pub const GLOBAL_SCOPE: usize = 0;
+
+/// Settings of the window the RenderSystem draws into
+#[derive(Debug, Clone)]
+pub struct WindowConfig {
+    pub title: String,
+    pub size: (u32, u32),
+    pub resizable: bool,
+    pub fullscreen: bool,
+    /// Synchronize presenting frames with the display's refresh rate
+    pub vsync: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: WINDOW_TITLE.to_string(),
+            size: WINDOW_SIZE,
+            resizable: false,
+            fullscreen: false,
+            vsync: false,
+        }
+    }
+}
 
 /// Handles rendering
-pub struct RenderSystem<'a> {
+pub struct RenderSystem {
     canvas: Canvas,
-    texture_creator: TextureCreator,
     background_color: Color,
-    render_components: BTreeMap<usize, RenderComponentInner<'a>>,
-    next_id: usize,
+    camera: Camera2D,
+    primitives: Primitives,
+    render_components: Arena<RenderComponentInner>,
+    textures: Arena<Texture>,
+    /// Textures loaded from files by their path
+    assets: HashMap<String, TextureAsset>,
+    /// Components whose `RenderComponent` was dropped, deleted the next time the system is used
+    deleted: Rc<RefCell<HashSet<ComponentHandle>>>,
+    next_sequence: usize,
+    scope: usize,
+    hidden_scopes: BTreeSet<usize>,
+    hidden_layers: BTreeSet<Layer>,
+    targets: Arena<RenderTarget>,
+    /// The next frame is read back before being presented
+    capture_requested: bool,
+    captured_frame: Option<Result<Image, AssetError>>,
+    /// Files the next frame is saved to
+    screenshots: Vec<PathBuf>,
+    saved_screenshots: Vec<(PathBuf, Result<(), AssetError>)>,
 }
 
 pub enum VideoError {
     NotInitialised,
 }
 
-impl<'a> RenderSystem<'a> {
-    pub fn new(sdl_context: &Sdl, window_size: Option<(u32, u32)>) -> RenderSystem<'a> {
-        let window_size = match window_size {
-            Some(x) => x,
-            None => WINDOW_SIZE,
-        };
+impl RenderSystem {
+    pub fn new(sdl_context: &Sdl, window_size: Option<(u32, u32)>) -> RenderSystem {
+        let mut config = WindowConfig::default();
+        if let Some(size) = window_size {
+            config.size = size;
+        }
+        RenderSystem::from_config(sdl_context, &config)
+    }
+
+    pub fn from_config(sdl_context: &Sdl, config: &WindowConfig) -> RenderSystem {
         let video_subsystem = sdl_context.video().unwrap();
-        let window = video_subsystem
-            .window("Blight Engine", window_size.0, window_size.1)
-            .position_centered()
-            .opengl()
-            .build()
-            .unwrap();
-        let canvas = window.into_canvas().build().unwrap();
+        let mut window = video_subsystem.window(&config.title, config.size.0, config.size.1);
+        window.position_centered().opengl();
+        if config.resizable {
+            window.resizable();
+        }
+        if config.fullscreen {
+            window.fullscreen();
+        }
+        let window = window.build().unwrap();
+        let mut canvas = window.into_canvas();
+        if config.vsync {
+            canvas = canvas.present_vsync();
+        }
+        RenderSystem::from_canvas(Canvas::Window(canvas.build().unwrap()), config.size)
+    }
+
+    /// Create a RenderSystem drawing in software into an offscreen surface
+    /// It needs neither a display nor SDL's video subsystem, so SDL does not have to be
+    /// initialized
+    pub fn headless(config: &WindowConfig) -> RenderSystem {
+        let surface = Surface::new(config.size.0, config.size.1, RGBA32).unwrap();
+        let canvas = Canvas::Surface(surface.into_canvas().unwrap());
+        RenderSystem::from_canvas(canvas, config.size)
+    }
+
+    /// Create a headless RenderSystem of `size`
+    /// Useful to render frames in tests, see `testing::render_offscreen`
+    pub fn offscreen(size: (u32, u32)) -> RenderSystem {
+        let config = WindowConfig {
+            size: size,
+            ..WindowConfig::default()
+        };
+        RenderSystem::headless(&config)
+    }
+
+    fn from_canvas(canvas: Canvas, size: (u32, u32)) -> RenderSystem {
         RenderSystem {
-            texture_creator: canvas.texture_creator(),
+            camera: Camera2D::new(Rect::new(0, 0, size.0, size.1)),
             canvas: canvas,
             background_color: Color::RGB(0, 0, 0),
-            render_components: BTreeMap::new(),
-            next_id: 0,
+            primitives: Primitives::new(),
+            render_components: Arena::new(),
+            textures: Arena::new(),
+            assets: HashMap::new(),
+            deleted: Rc::new(RefCell::new(HashSet::new())),
+            next_sequence: 0,
+            scope: GLOBAL_SCOPE,
+            hidden_scopes: BTreeSet::new(),
+            hidden_layers: BTreeSet::new(),
+            targets: Arena::new(),
+            capture_requested: false,
+            captured_frame: None,
+            screenshots: vec![],
+            saved_screenshots: vec![],
         }
     }
 
@@ -50,116 +147,1179 @@ impl<'a> RenderSystem<'a> {
         &mut self.canvas
     }
 
+    /// The camera world-space components are drawn through
+    pub fn get_camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    pub fn get_camera_mut(&mut self) -> &mut Camera2D {
+        &mut self.camera
+    }
+
+    /// Shapes drawn on top of the components in the next frame
+    pub fn get_primitives(&self) -> &Primitives {
+        &self.primitives
+    }
+
+    pub fn get_primitives_mut(&mut self) -> &mut Primitives {
+        &mut self.primitives
+    }
+
     pub fn set_background_color(&mut self, background: Option<Color>) {
         self.background_color = background.unwrap_or(Color::RGB(0, 0, 0));
     }
 
     /// Render all the components in the system
     pub fn render(&mut self) {
+        self.render_interpolated(1.0);
+    }
+
+    /// Render all the visible components, ordered by their layer and `z`
+    /// The enabled render targets are drawn into first, so components can show them
+    /// Components with `interpolate` set are drawn `alpha` of the way between their previous
+    /// and current positions
+    /// World-space components are transformed by the camera and clipped to its viewport
+    /// The queued primitives are drawn last, then the frame is read back if it was requested by
+    /// `capture_next_frame` or `save_screenshot`
+    pub fn render_interpolated(&mut self, alpha: f32) {
+        self.delete_dropped_components();
+        self.render_targets(alpha);
         self.clear();
-        // Take ownership of `render_components`
-        let components = std::mem::replace(&mut self.render_components, BTreeMap::new());
-        components.values().for_each(|component| unsafe {
-            self.render_texture(&*component.texture, &component.position);
-        });
-        // Return owrnership to the render system
-        self.render_components = components;
-        self.canvas.present();
+        let handles = self.draw_order();
+        let size = self.canvas.output_size().unwrap();
+        {
+            let (textures, components) = (&mut self.textures, &self.render_components);
+            let camera = &self.camera;
+            with_canvas!(self.canvas, ref mut canvas => {
+                draw_components(canvas, textures, components, &handles, camera, size, alpha);
+                canvas.set_clip_rect(None);
+            });
+        }
+        self.primitives.flush(&mut self.canvas, &self.camera);
+        self.capture_frame();
+        with_canvas!(self.canvas, ref mut canvas => canvas.present());
     }
 
-    fn clear(&mut self) {
-        self.canvas.set_draw_color(self.background_color);
-        self.canvas.clear();
+    /// Draw the layers of the enabled render targets into their textures, in the order the
+    /// targets were created
+    fn render_targets(&mut self, alpha: f32) {
+        let mut targets: Vec<(usize, RenderTargetHandle)> = self
+            .targets
+            .iter()
+            .filter(|(_, target)| target.enabled)
+            .map(|(index, target)| (target.sequence, RenderTargetHandle(index)))
+            .collect();
+        targets.sort();
+        for (_, handle) in targets {
+            let target = self.targets.get(handle.0).unwrap().clone();
+            let index = target.get_texture().0;
+            // Moved out while it is drawn into, the other textures are drawn from the arena
+            let mut texture = match self.textures.take(index) {
+                Some(texture) => texture,
+                None => continue,
+            };
+            let handles = self.target_draw_order(handle);
+            {
+                let (textures, components) = (&mut self.textures, &self.render_components);
+                let size = target.get_size();
+                // Textures the renderer can not draw into are left as they are
+                let _ = with_canvas!(self.canvas, ref mut canvas => {
+                    canvas.with_texture_canvas(&mut texture, |canvas| {
+                        canvas.set_draw_color(target.background_color);
+                        canvas.clear();
+                        draw_components(
+                            canvas, textures, components, &handles, &target.camera, size, alpha,
+                        );
+                        canvas.set_clip_rect(None);
+                    })
+                });
+            }
+            self.textures.restore(index, texture);
+        }
+    }
+
+    /// Read back the pixels of `rect`, or of the whole frame, drawn so far
+    /// Once a frame is presented its content is undefined, see `capture_next_frame` to read
+    /// complete frames
+    pub fn read_pixels(&self, rect: Option<Rect>) -> Result<Image, AssetError> {
+        self.read_canvas(rect).map_err(AssetError::Sdl)
     }
 
-    /// Borrow the render system's texture creator
-    pub fn texture_creator(&'a self) -> &'a TextureCreator {
-        &self.texture_creator
+    /// Read back the pixels of a texture created with `TextureAccess::Target`
+    pub fn read_texture(&mut self, handle: TextureHandle) -> Result<Image, AssetError> {
+        let texture = match self.textures.get_mut(handle.0) {
+            Some(texture) => texture,
+            None => return Err(AssetError::Sdl("Invalid texture handle".to_string())),
+        };
+        let query = texture.query();
+        let rect = Rect::new(0, 0, query.width, query.height);
+        let mut pixels = Err(String::new());
+        with_canvas!(self.canvas, ref mut canvas => {
+            canvas.with_texture_canvas(texture, |canvas| {
+                pixels = canvas.read_pixels(rect, RGBA32);
+            })
+        })
+        .map_err(|error| AssetError::Sdl(error.to_string()))?;
+        Ok(Image {
+            width: query.width,
+            height: query.height,
+            pixels: pixels.map_err(AssetError::Sdl)?,
+        })
     }
 
-    pub fn render_texture(&mut self, texture: &Texture<'a>, rect: &Rect) {
-        self.canvas.copy(texture, None, *rect).unwrap();
+    /// Read back the next rendered frame, see `take_captured_frame`
+    pub fn capture_next_frame(&mut self) {
+        self.capture_requested = true;
     }
 
-    pub fn create_texture(&'a mut self, size: &(u32, u32)) -> Texture {
-        let texture = self
-            .texture_creator()
-            .create_texture_target(None, size.0, size.1)
-            .unwrap();
-        texture
+    /// The frame read back since `capture_next_frame` was called
+    pub fn take_captured_frame(&mut self) -> Option<Result<Image, AssetError>> {
+        self.captured_frame.take()
     }
 
-    pub fn create_component(&'a mut self) -> RenderComponent {
-        assert!(self.next_id < <usize>::max_value());
-        self.next_id += 1;
-        let result = RenderComponentInner::new(self.next_id);
-        self.render_components.insert(self.next_id, result);
-        RenderComponent::new(self.next_id, self as *mut RenderSystem)
+    /// Save the next rendered frame to `path` as a BMP or a PNG, depending on its extension
+    /// Missing directories are created, see `get_saved_screenshots` for the outcome
+    pub fn save_screenshot<P>(&mut self, path: P)
+    where
+        P: Into<PathBuf>,
+    {
+        self.screenshots.push(path.into());
     }
 
-    pub fn get_components_by_ids(
-        &'a mut self,
-        ids: &[usize],
-    ) -> Vec<&mut RenderComponentInner<'a>> {
-        self.render_components
-            .values_mut()
-            .filter(|component| {
-                let id = component.id;
-                ids.iter().any(|i| *i == id)
+    /// The files the last rendered frame was saved to, with the outcome of saving it
+    pub fn get_saved_screenshots(&self) -> &[(PathBuf, Result<(), AssetError>)] {
+        &self.saved_screenshots
+    }
+
+    fn capture_frame(&mut self) {
+        self.saved_screenshots.clear();
+        if !self.capture_requested && self.screenshots.is_empty() {
+            return;
+        }
+        let frame = self.read_canvas(None);
+        for path in self.screenshots.drain(..) {
+            let saved = frame
+                .as_ref()
+                .map_err(|error| AssetError::Sdl(error.clone()))
+                .and_then(|image| save_image(image, &path));
+            self.saved_screenshots.push((path, saved));
+        }
+        if self.capture_requested {
+            self.capture_requested = false;
+            self.captured_frame = Some(frame.map_err(AssetError::Sdl));
+        }
+    }
+
+    fn read_canvas(&self, rect: Option<Rect>) -> Result<Image, String> {
+        let (width, height) = match rect {
+            Some(rect) => (rect.width(), rect.height()),
+            None => self.canvas.output_size()?,
+        };
+        Ok(Image {
+            width: width,
+            height: height,
+            pixels: with_canvas!(self.canvas, ref canvas => canvas.read_pixels(rect, RGBA32))?,
+        })
+    }
+
+    /// The visible components in the order they are drawn
+    pub fn draw_order(&self) -> Vec<ComponentHandle> {
+        self.sorted_components(|component| !self.hidden_layers.contains(&component.layer))
+    }
+
+    /// The components drawn into the render target of `handle`, in the order they are drawn
+    /// Layers hidden with `set_layer_visible` are still drawn into render targets, components
+    /// showing the target's own texture are not
+    pub fn target_draw_order(&self, handle: RenderTargetHandle) -> Vec<ComponentHandle> {
+        let target = match self.targets.get(handle.0) {
+            Some(target) => target,
+            None => return vec![],
+        };
+        self.sorted_components(|component| {
+            target.layers.contains(&component.layer)
+                && component.texture != Some(target.get_texture())
+        })
+    }
+
+    /// The components of visible scopes accepted by `filter`, ordered by their layer and `z`
+    fn sorted_components<F>(&self, filter: F) -> Vec<ComponentHandle>
+    where
+        F: Fn(&RenderComponentInner) -> bool,
+    {
+        let mut components: Vec<(ComponentHandle, &RenderComponentInner)> = self
+            .render_components
+            .iter()
+            .map(|(index, component)| (ComponentHandle(index), component))
+            .filter(|(handle, component)| {
+                !self.is_dropped(*handle)
+                    && !self.hidden_scopes.contains(&component.scope)
+                    && filter(component)
             })
-            .collect()
+            .collect();
+        components.sort_by_key(|(_, component)| component.draw_order());
+        components.into_iter().map(|(handle, _)| handle).collect()
+    }
+
+    /// Advance the animated tiles of every tilemap by `delta_time`
+    /// Called by the engine on every fixed update
+    pub fn update_tilemaps(&mut self, delta_time: &Duration) {
+        self.render_components
+            .iter_mut()
+            .for_each(|(_, component)| {
+                if let Some(ref mut tilemap) = component.tilemap {
+                    tilemap.update(delta_time);
+                }
+            });
     }
 
-    pub fn get_component_by_id(&'a mut self, id: usize) -> &mut RenderComponentInner<'a> {
+    /// Store the current position of every component as its previous position
+    /// Called by the engine at the start of every fixed update
+    pub fn save_positions(&mut self) {
+        self.delete_dropped_components();
         self.render_components
-            .get_mut(&id)
-            .expect(&format!("No component exists by the id [{}]", id))
+            .iter_mut()
+            .for_each(|(_, component)| {
+                component.previous_position = component.position;
+            });
+    }
+
+    fn clear(&mut self) {
+        let background = self.background_color;
+        with_canvas!(self.canvas, ref mut canvas => {
+            canvas.set_draw_color(background);
+            canvas.clear();
+        });
+    }
+
+    pub fn render_texture(&mut self, texture: &Texture, rect: &Rect) {
+        with_canvas!(self.canvas, ref mut canvas => canvas.copy(texture, None, *rect)).unwrap();
+    }
+
+    /// Create a texture that can be used as a render target
+    /// Pass it to `add_texture` to draw it with components
+    pub fn create_texture(&mut self, size: &(u32, u32)) -> Texture {
+        with_canvas!(self.canvas, ref canvas => canvas.create_texture_target(None, size.0, size.1))
+            .unwrap()
+    }
+
+    /// Create a render target of `size` pixels, it draws nothing until layers are added to it
+    /// Components show what was drawn into it by referencing its texture
+    pub fn create_render_target(&mut self, size: (u32, u32)) -> RenderTargetHandle {
+        let texture = self.create_texture(&size);
+        let texture = self.add_texture(texture);
+        let target = RenderTarget::new(texture, size, self.next_sequence);
+        self.next_sequence += 1;
+        RenderTargetHandle(self.targets.insert(target))
+    }
+
+    /// The render target of `handle`, `None` if it was deleted
+    pub fn try_get_target(&self, handle: RenderTargetHandle) -> Option<&RenderTarget> {
+        self.targets.get(handle.0)
+    }
+
+    pub fn try_get_target_mut(&mut self, handle: RenderTargetHandle) -> Option<&mut RenderTarget> {
+        self.targets.get_mut(handle.0)
+    }
+
+    /// Delete the render target of `handle` and its texture
+    pub fn delete_render_target(&mut self, handle: RenderTargetHandle) -> bool {
+        match self.targets.remove(handle.0) {
+            Some(target) => {
+                self.delete_texture(target.get_texture());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take ownership of `texture`, so components can reference it
+    pub fn add_texture(&mut self, texture: Texture) -> TextureHandle {
+        TextureHandle(self.textures.insert(texture))
+    }
+
+    /// The texture of `handle`, `None` if it was deleted
+    pub fn try_get_texture(&self, handle: TextureHandle) -> Option<&Texture> {
+        self.textures.get(handle.0)
+    }
+
+    pub fn try_get_texture_mut(&mut self, handle: TextureHandle) -> Option<&mut Texture> {
+        self.textures.get_mut(handle.0)
+    }
+
+    /// Delete and destroy the texture of `handle`, components referencing it are no longer drawn
+    /// Returns `false` if it was already deleted
+    pub fn delete_texture(&mut self, handle: TextureHandle) -> bool {
+        match self.textures.remove(handle.0) {
+            // Textures are not freed on drop, the canvas that created them is still alive
+            Some(texture) => {
+                unsafe { texture.destroy() };
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
     }
 
-    pub fn delete_components_by_ids(&mut self, ids: &[usize]) {
-        for id in ids {
-            self.render_components.remove(id);
+    /// Create a texture of `image`
+    pub fn create_texture_from_image(&mut self, image: &Image) -> Result<Texture, AssetError> {
+        let mut texture = with_canvas!(self.canvas, ref canvas => {
+            canvas.create_texture_static(RGBA32, image.width, image.height)
+        })
+        .map_err(|error| AssetError::Sdl(error.to_string()))?;
+        texture
+            .update(None, &image.pixels, image.width as usize * 4)
+            .map_err(|error| AssetError::Sdl(error.to_string()))?;
+        Ok(texture)
+    }
+
+    /// Load the BMP or PNG file at `path`
+    /// Files are loaded once, while the texture is loaded the same asset is returned
+    pub fn load_texture<P>(&mut self, path: P) -> Result<TextureAsset, AssetError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let key = path.to_string_lossy().into_owned();
+        if let Some(asset) = self.assets.get(&key) {
+            return Ok(asset.clone());
+        }
+        // Report missing files the same way for every format
+        fs::metadata(path)?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        let texture = match extension.as_deref() {
+            Some("png") => {
+                let image = Image::load_png(path)?;
+                self.create_texture_from_image(&image)?
+            }
+            Some("bmp") => {
+                let surface = Surface::load_bmp(path).map_err(AssetError::Sdl)?;
+                with_canvas!(self.canvas, ref canvas => canvas.create_texture_from_surface(&surface))
+                    .map_err(|error| AssetError::Sdl(error.to_string()))?
+            }
+            _ => return Err(AssetError::UnsupportedFormat(path.to_path_buf())),
+        };
+        let asset = TextureAsset::new(self.add_texture(texture));
+        self.assets.insert(key, asset.clone());
+        Ok(asset)
+    }
+
+    /// The loaded texture of the file at `path`
+    pub fn get_texture_asset<P>(&self, path: P) -> Option<TextureAsset>
+    where
+        P: AsRef<Path>,
+    {
+        let key = path.as_ref().to_string_lossy();
+        self.assets.get(key.as_ref()).cloned()
+    }
+
+    /// Delete the loaded textures no `TextureAsset` refers to anymore
+    /// Returns the number of textures deleted
+    pub fn unload_unused_textures(&mut self) -> usize {
+        let unused: Vec<String> = self
+            .assets
+            .iter()
+            .filter(|(_, asset)| asset.is_unique())
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in unused.iter() {
+            let asset = self.assets.remove(key).unwrap();
+            self.delete_texture(asset.handle());
         }
+        unused.len()
+    }
+
+    /// Number of textures loaded from files
+    pub fn texture_asset_count(&self) -> usize {
+        self.assets.len()
+    }
+
+    pub fn create_component(&mut self) -> RenderComponent {
+        self.delete_dropped_components();
+        let mut component = RenderComponentInner::new();
+        component.scope = self.scope;
+        component.sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let handle = ComponentHandle(self.render_components.insert(component));
+        RenderComponent::new(handle, Rc::downgrade(&self.deleted))
     }
 
+    /// The component of `handle`, `None` if it was deleted
+    pub fn try_get<H>(&self, handle: H) -> Option<&RenderComponentInner>
+    where
+        H: Into<ComponentHandle>,
+    {
+        let handle = handle.into();
+        if self.is_dropped(handle) {
+            return None;
+        }
+        self.render_components.get(handle.0)
+    }
+
+    pub fn try_get_mut<H>(&mut self, handle: H) -> Option<&mut RenderComponentInner>
+    where
+        H: Into<ComponentHandle>,
+    {
+        let handle = handle.into();
+        if self.is_dropped(handle) {
+            return None;
+        }
+        self.render_components.get_mut(handle.0)
+    }
+
+    /// Delete the component of `handle`, returns `false` if it was already deleted
+    pub fn delete_component<H>(&mut self, handle: H) -> bool
+    where
+        H: Into<ComponentHandle>,
+    {
+        let handle = handle.into();
+        self.deleted.borrow_mut().remove(&handle);
+        self.render_components.remove(handle.0).is_some()
+    }
+
+    /// Delete every component, the handles to them become stale
     pub fn purge_components(&mut self) {
         self.render_components.clear();
+        self.deleted.borrow_mut().clear();
+    }
+
+    pub fn component_count(&self) -> usize {
+        // Components dropped after a purge leave stale handles behind
+        let dropped = self
+            .deleted
+            .borrow()
+            .iter()
+            .filter(|handle| self.render_components.contains(handle.0))
+            .count();
+        self.render_components.len() - dropped
+    }
+
+    fn is_dropped(&self, handle: ComponentHandle) -> bool {
+        self.deleted.borrow().contains(&handle)
+    }
+
+    fn delete_dropped_components(&mut self) {
+        let deleted: Vec<ComponentHandle> = self.deleted.borrow_mut().drain().collect();
+        for handle in deleted {
+            self.render_components.remove(handle.0);
+        }
+    }
+
+    pub fn get_scope(&self) -> usize {
+        self.scope
+    }
+
+    /// Components created from now on belong to `scope`
+    /// Returns the previous scope
+    pub fn set_scope(&mut self, scope: usize) -> usize {
+        std::mem::replace(&mut self.scope, scope)
+    }
+
+    pub fn is_scope_visible(&self, scope: usize) -> bool {
+        !self.hidden_scopes.contains(&scope)
+    }
+
+    /// Show or hide every component of `scope`
+    pub fn set_scope_visible(&mut self, scope: usize, visible: bool) {
+        if visible {
+            self.hidden_scopes.remove(&scope);
+        } else {
+            self.hidden_scopes.insert(scope);
+        }
+    }
+
+    pub fn is_layer_visible(&self, layer: Layer) -> bool {
+        !self.hidden_layers.contains(&layer)
+    }
+
+    /// Show or hide every component in `layer`
+    /// Hidden layers are still drawn into the render targets they were added to
+    pub fn set_layer_visible(&mut self, layer: Layer, visible: bool) {
+        if visible {
+            self.hidden_layers.remove(&layer);
+        } else {
+            self.hidden_layers.insert(layer);
+        }
+    }
+
+    /// Delete every component of `scope`, including the ones whose `RenderComponent` is still
+    /// alive
+    pub fn purge_scope(&mut self, scope: usize) {
+        self.delete_dropped_components();
+        let handles: Vec<ComponentHandle> = self
+            .render_components
+            .iter()
+            .filter(|(_, component)| component.scope == scope)
+            .map(|(index, _)| ComponentHandle(index))
+            .collect();
+        for handle in handles {
+            self.delete_component(handle);
+        }
+        self.hidden_scopes.remove(&scope);
     }
 }
 
+/// Draw `handles` in order onto the current target of the canvas, of `size`
+fn draw_components<T>(
+    canvas: &mut render::Canvas<T>,
+    textures: &mut Arena<Texture>,
+    components: &Arena<RenderComponentInner>,
+    handles: &[ComponentHandle],
+    camera: &Camera2D,
+    size: (u32, u32),
+    alpha: f32,
+) where
+    T: render::RenderTarget,
+{
+    for handle in handles {
+        let handle = *handle;
+        let component = components.get(handle.0).unwrap();
+        let position = component.render_position(alpha);
+        let (position, angle, pivot, clip) = if component.screen_space {
+            (position, component.angle, component.pivot, None)
+        } else {
+            let zoom = camera.zoom;
+            let pivot = component.pivot.map(|pivot| {
+                Point::new(
+                    (pivot.x() as f32 * zoom).round() as i32,
+                    (pivot.y() as f32 * zoom).round() as i32,
+                )
+            });
+            (
+                camera.world_to_screen_rect(&position),
+                component.angle + camera.screen_angle(),
+                pivot,
+                Some(camera.viewport),
+            )
+        };
+        canvas.set_clip_rect(clip);
+        if let Some(ref text) = component.text {
+            // Glyphs are scaled with the text and rotated around its pivot
+            let scale_x = position.width() as f32 / text.width.max(1) as f32;
+            let scale_y = position.height() as f32 / text.height.max(1) as f32;
+            let pivot = pivot.unwrap_or_else(|| {
+                Point::new(position.width() as i32 / 2, position.height() as i32 / 2)
+            });
+            for glyph in text.glyphs.iter() {
+                let texture = match textures.get_mut(glyph.texture.0) {
+                    Some(texture) => texture,
+                    None => continue,
+                };
+                let offset = Point::new(
+                    (glyph.position.x() as f32 * scale_x).round() as i32,
+                    (glyph.position.y() as f32 * scale_y).round() as i32,
+                );
+                let destination = Rect::new(
+                    position.x() + offset.x(),
+                    position.y() + offset.y(),
+                    (glyph.position.width() as f32 * scale_x).round() as u32,
+                    (glyph.position.height() as f32 * scale_y).round() as u32,
+                );
+                let sprite = Sprite {
+                    source: Some(glyph.source),
+                    destination: destination,
+                    angle: angle,
+                    pivot: Some(pivot - offset),
+                    flip: (false, false),
+                };
+                draw(canvas, texture, &sprite, component);
+            }
+        } else if let Some(ref tilemap) = component.tilemap {
+            let texture = match textures.get_mut(tilemap.get_tileset().get_texture().0) {
+                Some(texture) => texture,
+                None => continue,
+            };
+            let origin = component.render_position(alpha).top_left();
+            let mut area = if component.screen_space {
+                Rect::new(0, 0, size.0, size.1)
+            } else {
+                camera.visible_area()
+            };
+            area.offset(-origin.x(), -origin.y());
+            for tile in tilemap.visible_tiles(&area) {
+                let mut destination = tile.position;
+                destination.offset(origin.x(), origin.y());
+                let (destination, angle) = if component.screen_space {
+                    (destination, 0.)
+                } else {
+                    (
+                        camera.world_to_screen_rect(&destination),
+                        camera.screen_angle(),
+                    )
+                };
+                let sprite = Sprite {
+                    source: Some(tile.source),
+                    destination: destination,
+                    angle: angle,
+                    pivot: None,
+                    flip: (tile.flip_h, tile.flip_v),
+                };
+                draw(canvas, texture, &sprite, component);
+            }
+        } else if let Some(TextureHandle(index)) = component.texture {
+            if let Some(texture) = textures.get_mut(index) {
+                let sprite = Sprite {
+                    source: component.source,
+                    destination: position,
+                    angle: angle,
+                    pivot: pivot,
+                    flip: (component.flip_h, component.flip_v),
+                };
+                draw(canvas, texture, &sprite, component);
+            }
+        }
+    }
+}
+
+/// Save `image` to `path`, creating its missing directories
+fn save_image(image: &Image, path: &Path) -> Result<(), AssetError> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    image.save(path)
+}
+
+/// Where and how a texture is drawn
+struct Sprite {
+    source: Option<Rect>,
+    destination: Rect,
+    angle: f64,
+    pivot: Option<Point>,
+    flip: (bool, bool),
+}
+
+/// Draw `sprite` of `texture` with the tint, alpha and blend mode of `component`
+fn draw<T>(
+    canvas: &mut render::Canvas<T>,
+    texture: &mut Texture,
+    sprite: &Sprite,
+    component: &RenderComponentInner,
+) where
+    T: render::RenderTarget,
+{
+    // Textures may be shared, so their modulation is set for every sprite
+    let tint = component.tint;
+    texture.set_color_mod(tint.r, tint.g, tint.b);
+    texture.set_alpha_mod(component.alpha);
+    texture.set_blend_mode(component.blend_mode);
+    // sdl2 0.31 panics when mirroring both ways, it combines the flags into an invalid
+    // `SDL_RendererFlip`, so it is drawn as the equivalent half turn
+    let (destination, angle, flip) = match sprite.flip {
+        (true, true) => {
+            let (destination, angle) = half_turn(sprite);
+            (destination, angle, (false, false))
+        }
+        flip => (sprite.destination, sprite.angle, flip),
+    };
+    canvas
+        .copy_ex(
+            texture,
+            sprite.source,
+            destination,
+            angle,
+            sprite.pivot,
+            flip.0,
+            flip.1,
+        )
+        .unwrap();
+}
+
+/// Destination and angle of `sprite` mirrored both ways, drawn without mirroring
+/// Mirroring is a half turn around the center of the destination, followed by the rotation
+/// around the pivot. Turning around the pivot instead moves the sprite by twice the offset of
+/// the center from the pivot, rotated by the angle
+fn half_turn(sprite: &Sprite) -> (Rect, f64) {
+    let mut destination = sprite.destination;
+    let center = (
+        f64::from(destination.width()) / 2.,
+        f64::from(destination.height()) / 2.,
+    );
+    let pivot = sprite
+        .pivot
+        .map_or(center, |pivot| (f64::from(pivot.x()), f64::from(pivot.y())));
+    let (x, y) = (center.0 - pivot.0, center.1 - pivot.1);
+    let (sin, cos) = sprite.angle.to_radians().sin_cos();
+    destination.offset(
+        (2. * (x * cos - y * sin)).round() as i32,
+        (2. * (x * sin + y * cos)).round() as i32,
+    );
+    (destination, sprite.angle + 180.)
+}
+
 #[cfg(test)]
 mod test {
+    use super::super::super::super::common::aabb::AABB;
+    use super::super::super::super::common::circle::Circle;
+    use super::super::super::super::common::vector2::Vector2;
+    use super::super::super::super::testing::TempDirectory;
     use super::*;
     use rand::prelude::*;
-    use sdl2;
     use test::Bencher;
 
+    fn headless() -> RenderSystem {
+        RenderSystem::headless(&WindowConfig::default())
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+    /// A texture of `pixels`, in rows of `width`
+    fn image_texture(
+        render_system: &mut RenderSystem,
+        width: u32,
+        pixels: &[[u8; 4]],
+    ) -> TextureHandle {
+        let image = Image {
+            width: width,
+            height: pixels.len() as u32 / width,
+            pixels: pixels.iter().flat_map(|pixel| pixel.to_vec()).collect(),
+        };
+        let texture = render_system.create_texture_from_image(&image).unwrap();
+        render_system.add_texture(texture)
+    }
+
+    fn pixel(image: &Image, x: u32, y: u32) -> &[u8] {
+        let start = ((y * image.width + x) * 4) as usize;
+        &image.pixels[start..start + 4]
+    }
+
+    #[test]
+    fn test_headless_renders_into_memory() {
+        let mut render_system = RenderSystem::offscreen((4, 3));
+        render_system.set_background_color(Some(Color::RGB(10, 20, 30)));
+
+        render_system.capture_next_frame();
+        render_system.render();
+
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        assert_eq!((frame.width, frame.height), (4, 3));
+        assert_eq!(&frame.pixels[..8], &[10, 20, 30, 255, 10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_handles_of_dropped_components_are_stale() {
+        let mut render_system = headless();
+        let component = render_system.create_component();
+        let handle = component.handle();
+        let kept = render_system.create_component();
+
+        assert!(render_system.try_get(handle).is_some());
+        drop(component);
+
+        assert!(render_system.try_get(handle).is_none());
+        assert!(render_system.try_get_mut(handle).is_none());
+        assert_eq!(render_system.component_count(), 1);
+
+        let reused = render_system.create_component();
+        assert!(render_system.try_get(handle).is_none());
+        assert!(render_system.try_get(&reused).is_some());
+        assert!(render_system.try_get(&kept).is_some());
+    }
+
+    #[test]
+    fn test_purging_invalidates_live_components() {
+        let mut render_system = headless();
+        let component = render_system.create_component();
+
+        render_system.purge_components();
+
+        assert!(render_system.try_get(&component).is_none());
+        assert!(!render_system.delete_component(&component));
+        drop(component);
+        assert_eq!(render_system.component_count(), 0);
+    }
+
+    #[test]
+    fn test_draw_order_follows_layers_and_skips_hidden_ones() {
+        let mut render_system = headless();
+        let player = render_system.create_component();
+        let hud = render_system.create_component();
+        let background = render_system.create_component();
+        render_system.try_get_mut(&hud).unwrap().layer = Layer::UI;
+        render_system.try_get_mut(&background).unwrap().layer = Layer::BACKGROUND;
+
+        assert_eq!(
+            render_system.draw_order(),
+            vec![background.handle(), player.handle(), hud.handle()]
+        );
+
+        render_system.set_layer_visible(Layer::UI, false);
+        assert!(!render_system.is_layer_visible(Layer::UI));
+        assert_eq!(
+            render_system.draw_order(),
+            vec![background.handle(), player.handle()]
+        );
+    }
+
+    #[test]
+    fn test_render_with_every_effect() {
+        let mut render_system = headless();
+        let background = [0, 0, 100, 255];
+        render_system.set_background_color(Some(Color::RGB(0, 0, 100)));
+        let texture = image_texture(&mut render_system, 2, &[WHITE, GREEN]);
+        let component = render_system.create_component();
+        {
+            let component = render_system.try_get_mut(&component).unwrap();
+            component.texture = Some(texture);
+            component.position = Rect::new(398, 298, 4, 2);
+            component.angle = 90.;
+            component.pivot = Some(Point::new(0, 0));
+            component.flip_h = true;
+            component.tint = Color::RGB(255, 0, 0);
+            component.alpha = 128;
+            component.blend_mode = BlendMode::Add;
+        }
+        render_system.get_camera_mut().zoom = 2.;
+
+        render_system.capture_next_frame();
+        render_system.render();
+
+        // Zoomed to (396, 296, 8, 4), mirrored, then turned clockwise around its top left
+        // The white half is tinted red and added at half strength, the green one adds nothing
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        let white = pixel(&frame, 394, 302);
+        assert!(white[0] >= 127 && white[0] <= 128, "{:?}", white);
+        assert_eq!(&white[1..], &[0, 100, 255]);
+        assert_eq!(pixel(&frame, 394, 297), background);
+        assert_eq!(pixel(&frame, 398, 297), background);
+    }
+
+    #[test]
+    fn test_deleted_textures_are_stale() {
+        let mut render_system = headless();
+        let texture = render_system.create_texture(&(4, 4));
+        let texture = render_system.add_texture(texture);
+        let component = render_system.create_component();
+        render_system.try_get_mut(&component).unwrap().texture = Some(texture);
+
+        assert!(render_system.delete_texture(texture));
+
+        assert!(render_system.try_get_texture(texture).is_none());
+        assert!(!render_system.delete_texture(texture));
+        assert_eq!(render_system.texture_count(), 0);
+        // Components referencing the deleted texture are skipped
+        render_system.render();
+    }
+
+    #[test]
+    fn test_primitives_are_drawn_once() {
+        let mut render_system = headless();
+        {
+            let primitives = render_system.get_primitives_mut();
+            primitives.outline(
+                &AABB::new(Vector2::new(30., 10.), 4., 4.),
+                Color::RGBA(255, 0, 0, 128),
+            );
+            primitives.set_space(Space::Screen);
+            primitives.fill(
+                &Circle::new(Vector2::new(10., 10.), 4.),
+                Color::RGB(0, 255, 0),
+            );
+        }
+
+        render_system.capture_next_frame();
+        render_system.render();
+
+        assert!(render_system.get_primitives().is_empty());
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        assert_eq!(pixel(&frame, 10, 10), GREEN);
+        assert_eq!(pixel(&frame, 13, 10), GREEN);
+        assert_eq!(pixel(&frame, 10, 7), GREEN);
+        assert_eq!(pixel(&frame, 15, 10), [0, 0, 0, 255]);
+        // The outline is blended, its inside is left empty
+        let edge = pixel(&frame, 28, 10);
+        assert!(edge[0] >= 127 && edge[0] <= 128, "{:?}", edge);
+        assert_eq!(pixel(&frame, 30, 10), [0, 0, 0, 255]);
+
+        render_system.capture_next_frame();
+        render_system.render();
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        assert_eq!(pixel(&frame, 10, 10), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let mut render_system = headless();
+        // A white "h", and an "i" whose bottom half is transparent
+        let pixels: Vec<[u8; 4]> = (0..8 * 16)
+            .map(|i| match (i % 16, i / 16) {
+                (x, y) if x >= 8 && y >= 4 => [0, 0, 0, 0],
+                _ => WHITE,
+            })
+            .collect();
+        let texture = image_texture(&mut render_system, 16, &pixels);
+        let font = BitmapFont::from_grid(&SpriteSheet::new(texture, (16, 8), (8, 8)), "hi");
+        let component = render_system.create_component();
+        {
+            let component = render_system.try_get_mut(&component).unwrap();
+            component.position = Rect::new(10, 10, 1, 1);
+            let style = TextStyle {
+                color: Color::RGB(255, 0, 0),
+                ..TextStyle::default()
+            };
+            component.set_text(&font, "hi\nih", &style);
+
+            assert_eq!(component.position, Rect::new(10, 10, 16, 16));
+            assert_eq!(component.tint, Color::RGB(255, 0, 0));
+            assert_eq!(component.text.as_ref().unwrap().glyphs.len(), 4);
+        }
+
+        render_system.capture_next_frame();
+        render_system.render();
+
+        // The glyphs are tinted by the color of the text
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        let black = [0, 0, 0, 255];
+        assert_eq!(pixel(&frame, 12, 12), RED);
+        assert_eq!(pixel(&frame, 20, 12), RED);
+        assert_eq!(pixel(&frame, 20, 16), black);
+        assert_eq!(pixel(&frame, 12, 19), RED);
+        assert_eq!(pixel(&frame, 12, 22), black);
+        assert_eq!(pixel(&frame, 20, 22), RED);
+        assert_eq!(pixel(&frame, 27, 12), black);
+
+        render_system.try_get_mut(&component).unwrap().angle = 45.;
+        render_system.get_camera_mut().zoom = 2.;
+        render_system.render();
+    }
+
+    #[test]
+    fn test_render_tilemap() {
+        let mut render_system = headless();
+        // A green tile, and a blue one with a red top left quarter
+        let pixels: Vec<[u8; 4]> = (0..8 * 16)
+            .map(|i| match (i % 16, i / 16) {
+                (x, _) if x < 8 => GREEN,
+                (x, y) if x < 12 && y < 4 => RED,
+                _ => BLUE,
+            })
+            .collect();
+        let texture = image_texture(&mut render_system, 16, &pixels);
+        let tileset = Tileset::from_sprite_sheet(&SpriteSheet::new(texture, (16, 8), (8, 8)));
+        let mut tilemap = Tilemap::new(tileset, (100, 100), (8, 8));
+        let layer = tilemap.add_layer("ground");
+        tilemap
+            .get_layer_mut(layer)
+            .unwrap()
+            .fill(Some(Tile::new(0)));
+        tilemap.set_tile(layer, 3, 4, Some(Tile::flipped(1, true, true)));
+        let component = render_system.create_component();
+        {
+            let component = render_system.try_get_mut(&component).unwrap();
+            component.position = Rect::new(-20, -20, 1, 1);
+            component.set_tilemap(tilemap);
+
+            assert_eq!(component.position, Rect::new(-20, -20, 800, 800));
+        }
+
+        render_system.update_tilemaps(&Duration::from_millis(16));
+        render_system.capture_next_frame();
+        render_system.render();
+
+        // The flipped tile covers (4, 12) to (12, 20), its red quarter is at the bottom right
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        assert_eq!(pixel(&frame, 0, 0), GREEN);
+        assert_eq!(pixel(&frame, 5, 13), BLUE);
+        assert_eq!(pixel(&frame, 10, 18), RED);
+        assert_eq!(pixel(&frame, 12, 18), GREEN);
+
+        render_system.get_camera_mut().rotation = 30.;
+        render_system.render();
+    }
+
+    #[test]
+    fn test_mirroring_both_ways_keeps_the_pivot() {
+        let mut render_system = RenderSystem::offscreen((16, 16));
+        let texture = image_texture(&mut render_system, 2, &[RED, GREEN, BLUE, WHITE]);
+        let component = render_system.create_component();
+        {
+            let component = render_system.try_get_mut(&component).unwrap();
+            component.texture = Some(texture);
+            component.position = Rect::new(10, 10, 2, 2);
+            component.screen_space = true;
+            component.flip_h = true;
+            component.flip_v = true;
+            component.angle = 90.;
+            component.pivot = Some(Point::new(0, 0));
+        }
+
+        render_system.capture_next_frame();
+        render_system.render();
+
+        // Mirrored into [WHITE, BLUE, GREEN, RED], then turned clockwise around (10, 10)
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        assert_eq!(pixel(&frame, 9, 10), WHITE);
+        assert_eq!(pixel(&frame, 9, 11), BLUE);
+        assert_eq!(pixel(&frame, 8, 10), GREEN);
+        assert_eq!(pixel(&frame, 8, 11), RED);
+        assert_eq!(pixel(&frame, 10, 10), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_render_targets_draw_their_layers() {
+        let mut render_system = headless();
+        let red = image_texture(&mut render_system, 1, &[RED]);
+        let world = render_system.create_component();
+        {
+            let world = render_system.try_get_mut(&world).unwrap();
+            world.texture = Some(red);
+            world.position = Rect::new(0, 0, 10, 10);
+        }
+        let hud = render_system.create_component();
+        render_system.try_get_mut(&hud).unwrap().layer = Layer::UI;
+        let target = render_system.create_render_target((200, 150));
+        let texture = {
+            let target = render_system.try_get_target_mut(target).unwrap();
+            target.layers.push(Layer::WORLD);
+            target.get_texture()
+        };
+        render_system.set_layer_visible(Layer::WORLD, false);
+        let screen = render_system.create_component();
+        {
+            let screen = render_system.try_get_mut(&screen).unwrap();
+            screen.texture = Some(texture);
+            screen.position = Rect::new(0, 0, 800, 600);
+            screen.layer = Layer::BACKGROUND;
+        }
+
+        assert_eq!(
+            render_system.target_draw_order(target),
+            vec![world.handle()]
+        );
+        assert_eq!(
+            render_system.draw_order(),
+            vec![screen.handle(), hud.handle()]
+        );
+        render_system.capture_next_frame();
+        render_system.render();
+
+        let drawn = render_system.read_texture(texture).unwrap();
+        assert_eq!((drawn.width, drawn.height), (200, 150));
+        assert_eq!(pixel(&drawn, 5, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&drawn, 100, 100), [0, 0, 0, 0]);
+        // The target is stretched over the window
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        assert_eq!(pixel(&frame, 20, 20), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 400, 400), [0, 0, 0, 255]);
+
+        render_system.try_get_mut(&screen).unwrap().layer = Layer::WORLD;
+        render_system
+            .try_get_target_mut(target)
+            .unwrap()
+            .layers
+            .push(Layer::UI);
+        assert_eq!(
+            render_system.target_draw_order(target),
+            vec![world.handle(), hud.handle()]
+        );
+        assert!(render_system.delete_render_target(target));
+        assert!(render_system.try_get_texture(texture).is_none());
+        assert!(render_system.target_draw_order(target).is_empty());
+    }
+
+    #[test]
+    fn test_capture_frames() {
+        let mut render_system = headless();
+        render_system.set_background_color(Some(Color::RGB(10, 20, 30)));
+        let temp = TempDirectory::new("capture_frames");
+        let path = temp.path().join("screenshots").join("frame.png");
+
+        let image = render_system
+            .read_pixels(Some(Rect::new(0, 0, 2, 3)))
+            .unwrap();
+        assert_eq!((image.width, image.height, image.pixels.len()), (2, 3, 24));
+
+        render_system.capture_next_frame();
+        render_system.save_screenshot(path.clone());
+        render_system.render();
+
+        let frame = render_system.take_captured_frame().unwrap().unwrap();
+        assert_eq!((frame.width, frame.height), WINDOW_SIZE);
+        assert_eq!(&frame.pixels[..4], &[10, 20, 30, 255]);
+        {
+            let saved = render_system.get_saved_screenshots();
+            assert_eq!(saved[0].0, path);
+            assert!(saved[0].1.is_ok());
+        }
+        assert_eq!(Image::load_png(&path).unwrap(), frame);
+        render_system.render();
+        assert!(render_system.take_captured_frame().is_none());
+        assert!(render_system.get_saved_screenshots().is_empty());
+    }
+
+    #[test]
+    fn test_loaded_textures_are_cached_until_unused() {
+        let temp = TempDirectory::new("loaded_texture");
+        let path = temp.path().join("texture.png");
+        {
+            let file = fs::File::create(&path).unwrap();
+            let mut encoder = ::png::Encoder::new(file, 1, 1);
+            encoder.set_color(::png::ColorType::Rgba);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[255, 0, 0, 255]).unwrap();
+        }
+        let mut render_system = headless();
+
+        let asset = render_system.load_texture(&path).unwrap();
+        let again = render_system.load_texture(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(asset, again);
+        assert_eq!(render_system.get_texture_asset(&path), Some(asset.clone()));
+        assert_eq!(render_system.unload_unused_textures(), 0);
+        drop(asset);
+        drop(again);
+        assert_eq!(render_system.unload_unused_textures(), 1);
+        assert_eq!(render_system.texture_asset_count(), 0);
+        assert_eq!(render_system.texture_count(), 0);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let mut render_system = headless();
+
+        match render_system.load_texture("missing.png") {
+            Err(AssetError::Io(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match render_system.load_texture("Cargo.toml") {
+            Err(AssetError::UnsupportedFormat(path)) => assert_eq!(path, Path::new("Cargo.toml")),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(render_system.texture_count(), 0);
+    }
+
     #[bench]
-    fn simple_render_bunch<'a>(bencher: &mut Bencher) {
-        let sdl = sdl2::init().unwrap();
-        let mut render_system = RenderSystem::new(&sdl, None);
+    fn simple_render_bunch(bencher: &mut Bencher) {
+        let mut render_system = headless();
         render_system.set_background_color(Some(Color::RGB(255, 255, 255)));
-        let render_ptr = &mut render_system as *mut RenderSystem;
-        unsafe {
-            let mut components = vec![];
-            const TEXTURE_SIZE: u32 = 50;
-            let mut texture = (*render_ptr).create_texture(&(TEXTURE_SIZE, TEXTURE_SIZE));
-
-            let mut rng = thread_rng();
-            for _ in 0..100 {
-                let mut component = (*render_ptr).create_component();
-                component.texture = &mut texture;
-                components.push(component);
-            }
+        let mut components = vec![];
+        const TEXTURE_SIZE: u32 = 50;
+        let texture = render_system.create_texture(&(TEXTURE_SIZE, TEXTURE_SIZE));
+        let texture = render_system.add_texture(texture);
 
-            bencher.iter(|| {
-                components.iter_mut().for_each(|component| {
-                    let x = rng.gen_range::<i32>(50, 500);
-                    let y = rng.gen_range::<i32>(50, 500);
-                    component.position = Rect::new(x, y, TEXTURE_SIZE, TEXTURE_SIZE);
-                });
-                render_system.render();
-            })
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let component = render_system.create_component();
+            render_system.try_get_mut(&component).unwrap().texture = Some(texture);
+            components.push(component);
         }
+
+        bencher.iter(|| {
+            components.iter().for_each(|component| {
+                let x = rng.gen_range::<i32>(50, 500);
+                let y = rng.gen_range::<i32>(50, 500);
+                let component = render_system.try_get_mut(component).unwrap();
+                component.position = Rect::new(x, y, TEXTURE_SIZE, TEXTURE_SIZE);
+            });
+            render_system.render();
+        })
     }
 }
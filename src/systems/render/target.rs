@@ -0,0 +1,60 @@
+use super::{Camera2D, Color, Layer, Rect, TextureHandle};
+
+/// Texture some layers are drawn into before every frame, e.g. for minimaps, split screens or
+/// low resolution rendering scaled up to the window
+/// Components show the target by referencing its texture, like any other sprite
+///```no_run
+/// use blight::systems::render::{Layer, Rect, RenderSystem};
+///
+/// let mut render_system = RenderSystem::offscreen((800, 600));
+/// // Draw the world at a quarter of the resolution, then scale it up to the window
+/// let target = render_system.create_render_target((200, 150));
+/// let texture = {
+///     let target = render_system.try_get_target_mut(target).unwrap();
+///     target.layers.push(Layer::WORLD);
+///     target.camera.zoom = 0.25;
+///     target.get_texture()
+/// };
+/// render_system.set_layer_visible(Layer::WORLD, false);
+/// let screen = render_system.create_component();
+/// let component = render_system.try_get_mut(&screen).unwrap();
+/// component.texture = Some(texture);
+/// component.position = Rect::new(0, 0, 800, 600);
+/// component.screen_space = true;
+///```
+#[derive(Debug, Clone)]
+pub struct RenderTarget {
+    texture: TextureHandle,
+    size: (u32, u32),
+    pub(crate) sequence: usize,
+    /// Layers drawn into the target
+    pub layers: Vec<Layer>,
+    /// Camera world-space components are drawn through, its viewport covers the target
+    pub camera: Camera2D,
+    /// Color the target is cleared with before drawing, transparent by default
+    pub background_color: Color,
+    /// Disabled targets are not drawn into and keep their last content
+    pub enabled: bool,
+}
+
+impl RenderTarget {
+    pub(crate) fn new(texture: TextureHandle, size: (u32, u32), sequence: usize) -> RenderTarget {
+        RenderTarget {
+            texture: texture,
+            size: size,
+            sequence: sequence,
+            layers: vec![],
+            camera: Camera2D::new(Rect::new(0, 0, size.0, size.1)),
+            background_color: Color::RGBA(0, 0, 0, 0),
+            enabled: true,
+        }
+    }
+
+    pub fn get_texture(&self) -> TextureHandle {
+        self.texture
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+}
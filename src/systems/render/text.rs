@@ -0,0 +1,562 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{
+    AssetError, Color, Point, Rect, RenderSystem, SpriteSheet, TextureAsset, TextureHandle,
+};
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    Asset(AssetError),
+    /// The line of the font file at this number, counted from 1, is invalid
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// A glyph is on a page the font has no texture for
+    MissingPage(usize),
+}
+
+impl From<io::Error> for FontError {
+    fn from(error: io::Error) -> Self {
+        FontError::Io(error)
+    }
+}
+
+impl From<AssetError> for FontError {
+    fn from(error: AssetError) -> Self {
+        FontError::Asset(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// How a text is laid out
+#[derive(Debug, Clone)]
+pub struct TextStyle {
+    /// Alignment of the lines inside the text
+    pub align: TextAlign,
+    /// Lines longer than this are broken at spaces
+    pub wrap_width: Option<u32>,
+    /// Pixels added between lines, on top of the font's line height
+    pub line_spacing: i32,
+    pub color: Color,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            align: TextAlign::Left,
+            wrap_width: None,
+            line_spacing: 0,
+            color: Color::RGB(255, 255, 255),
+        }
+    }
+}
+
+/// A character of a `BitmapFont`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    /// Index of the texture the glyph is on
+    pub page: usize,
+    /// Region of the page, `None` for glyphs drawing nothing like spaces
+    pub source: Option<Rect>,
+    /// Offset of the glyph from the pen, relative to the top of the line
+    pub offset: Point,
+    /// How far the pen moves after the glyph
+    pub advance: i32,
+}
+
+/// A glyph placed by `BitmapFont::layout`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedGlyph {
+    pub texture: TextureHandle,
+    pub source: Rect,
+    /// Position relative to the top left of the text
+    pub position: Rect,
+}
+
+/// Text laid out in a font, ready to be drawn by a render component
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<PlacedGlyph>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Font whose glyphs are regions of textures
+/// Load a BMFont `.fnt` file with `load_fnt`, or slice a grid of glyphs with `from_grid`
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    pages: Vec<TextureHandle>,
+    /// Keep the pages loaded by `load_fnt` alive
+    assets: Vec<TextureAsset>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    line_height: u32,
+}
+
+impl BitmapFont {
+    pub fn new(line_height: u32) -> BitmapFont {
+        BitmapFont {
+            pages: vec![],
+            assets: vec![],
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+            line_height: line_height,
+        }
+    }
+
+    /// Font of the frames of `sheet`, the nth frame is the nth character of `characters`
+    /// Every glyph is as wide as a frame
+    pub fn from_grid(sheet: &SpriteSheet, characters: &str) -> BitmapFont {
+        let height = sheet.frame(0).map_or(0, |frame| frame.height());
+        let mut font = BitmapFont::new(height);
+        let page = font.add_page(sheet.get_texture());
+        for (index, character) in characters.chars().enumerate() {
+            if let Some(frame) = sheet.frame(index) {
+                font.glyphs.insert(
+                    character,
+                    Glyph {
+                        page: page,
+                        source: Some(frame),
+                        offset: Point::new(0, 0),
+                        advance: frame.width() as i32,
+                    },
+                );
+            }
+        }
+        font
+    }
+
+    /// Parse a BMFont file in the text format, `pages` are the textures of its pages in order
+    pub fn parse_fnt(source: &str, pages: &[TextureHandle]) -> Result<BitmapFont, FontError> {
+        let (mut font, files) = parse_fnt(source)?;
+        if pages.len() < files.len() {
+            return Err(FontError::MissingPage(pages.len()));
+        }
+        font.pages = pages.to_vec();
+        font.check_pages()?;
+        Ok(font)
+    }
+
+    /// Load a BMFont file in the text format, its pages are loaded relative to it
+    pub fn load_fnt<P>(render_system: &mut RenderSystem, path: P) -> Result<BitmapFont, FontError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let (mut font, files) = parse_fnt(&source)?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        for file in files {
+            let asset = render_system.load_texture(directory.join(file))?;
+            font.pages.push(asset.handle());
+            font.assets.push(asset);
+        }
+        font.check_pages()?;
+        Ok(font)
+    }
+
+    /// Add a texture glyphs can be on, returns its page index
+    pub fn add_page(&mut self, texture: TextureHandle) -> usize {
+        self.pages.push(texture);
+        self.pages.len() - 1
+    }
+
+    /// Returns an error if the glyph is on a page the font has no texture for
+    pub fn insert_glyph(&mut self, character: char, glyph: Glyph) -> Result<(), FontError> {
+        if glyph.page >= self.pages.len() {
+            return Err(FontError::MissingPage(glyph.page));
+        }
+        self.glyphs.insert(character, glyph);
+        Ok(())
+    }
+
+    pub fn get_glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.get(&character)
+    }
+
+    /// Move the pen by `amount` between `first` and `second`
+    pub fn set_kerning(&mut self, first: char, second: char, amount: i32) {
+        self.kerning.insert((first, second), amount);
+    }
+
+    pub fn get_kerning(&self, first: char, second: char) -> i32 {
+        self.kerning.get(&(first, second)).cloned().unwrap_or(0)
+    }
+
+    pub fn get_line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    /// Size of `text` laid out in `style`
+    pub fn measure(&self, text: &str, style: &TextStyle) -> (u32, u32) {
+        let lines = self.lines(text, style.wrap_width);
+        let width = lines
+            .iter()
+            .map(|line| self.line_width(line))
+            .max()
+            .unwrap_or(0);
+        (
+            style.wrap_width.unwrap_or(width),
+            self.text_height(lines.len(), style),
+        )
+    }
+
+    /// Width of a single line of `text`, ignoring line breaks and wrapping
+    pub fn line_width(&self, text: &str) -> u32 {
+        let mut width = 0;
+        let mut previous = None;
+        for character in text.chars() {
+            if let Some(previous) = previous {
+                width += self.get_kerning(previous, character);
+            }
+            width += self.glyphs.get(&character).map_or(0, |glyph| glyph.advance);
+            previous = Some(character);
+        }
+        width.max(0) as u32
+    }
+
+    /// Place the glyphs of `text`
+    /// Characters the font has no glyph for are skipped
+    pub fn layout(&self, text: &str, style: &TextStyle) -> TextLayout {
+        let lines = self.lines(text, style.wrap_width);
+        let (width, height) = self.measure(text, style);
+        let mut glyphs = vec![];
+        for (row, line) in lines.iter().enumerate() {
+            let line_width = self.line_width(line) as i32;
+            let mut pen = match style.align {
+                TextAlign::Left => 0,
+                TextAlign::Center => (width as i32 - line_width) / 2,
+                TextAlign::Right => width as i32 - line_width,
+            };
+            let top = row as i32 * (self.line_height as i32 + style.line_spacing);
+            let mut previous = None;
+            for character in line.chars() {
+                let glyph = match self.glyphs.get(&character) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+                if let Some(previous) = previous {
+                    pen += self.get_kerning(previous, character);
+                }
+                if let Some(source) = glyph.source {
+                    glyphs.push(PlacedGlyph {
+                        texture: self.pages[glyph.page],
+                        source: source,
+                        position: Rect::new(
+                            pen + glyph.offset.x(),
+                            top + glyph.offset.y(),
+                            source.width(),
+                            source.height(),
+                        ),
+                    });
+                }
+                pen += glyph.advance;
+                previous = Some(character);
+            }
+        }
+        TextLayout {
+            glyphs: glyphs,
+            width: width,
+            height: height,
+        }
+    }
+
+    /// Split `text` into lines at line breaks and, if given, at spaces before `wrap_width`
+    fn lines(&self, text: &str, wrap_width: Option<u32>) -> Vec<String> {
+        let mut lines = vec![];
+        for paragraph in text.split('\n') {
+            let paragraph = paragraph.trim_end_matches('\r');
+            let wrap_width = match wrap_width {
+                Some(wrap_width) => wrap_width,
+                None => {
+                    lines.push(paragraph.to_string());
+                    continue;
+                }
+            };
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", line, word)
+                };
+                // Words wider than the wrap width get a line of their own
+                if !line.is_empty() && self.line_width(&candidate) > wrap_width {
+                    lines.push(line);
+                    line = word.to_string();
+                } else {
+                    line = candidate;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn text_height(&self, lines: usize, style: &TextStyle) -> u32 {
+        let lines = lines as i32;
+        let height = lines * self.line_height as i32 + (lines - 1) * style.line_spacing;
+        height.max(0) as u32
+    }
+
+    fn check_pages(&self) -> Result<(), FontError> {
+        match self
+            .glyphs
+            .values()
+            .find(|glyph| glyph.page >= self.pages.len())
+        {
+            Some(glyph) => Err(FontError::MissingPage(glyph.page)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse a BMFont text file into a font without pages and the file names of its pages
+fn parse_fnt(source: &str) -> Result<(BitmapFont, Vec<String>), FontError> {
+    let mut font = BitmapFont::new(0);
+    let mut files = vec![];
+    for (number, line) in source.lines().enumerate() {
+        let number = number + 1;
+        let mut tokens = tokenize(line).into_iter();
+        let tag = match tokens.next() {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let attributes: HashMap<String, String> = tokens
+            .filter_map(|token| {
+                let mut parts = token.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) => Some((key.to_string(), value.to_string())),
+                    _ => None,
+                }
+            })
+            .collect();
+        match tag.as_str() {
+            "common" => font.line_height = attribute(&attributes, "lineHeight", number)?,
+            "page" => {
+                let id: usize = attribute(&attributes, "id", number)?;
+                let file = attributes.get("file").cloned().ok_or(FontError::Parse {
+                    line: number,
+                    message: "page without a file".to_string(),
+                })?;
+                if id != files.len() {
+                    return Err(FontError::Parse {
+                        line: number,
+                        message: format!("page {} is not numbered after page {}", id, files.len()),
+                    });
+                }
+                files.push(file);
+            }
+            "char" => {
+                let id: u32 = attribute(&attributes, "id", number)?;
+                let character = ::std::char::from_u32(id).ok_or(FontError::Parse {
+                    line: number,
+                    message: format!("{} is not a character", id),
+                })?;
+                let (width, height): (u32, u32) = (
+                    attribute(&attributes, "width", number)?,
+                    attribute(&attributes, "height", number)?,
+                );
+                let source = if width > 0 && height > 0 {
+                    Some(Rect::new(
+                        attribute(&attributes, "x", number)?,
+                        attribute(&attributes, "y", number)?,
+                        width,
+                        height,
+                    ))
+                } else {
+                    None
+                };
+                // The pages are added after parsing, `check_pages` validates the glyphs then
+                font.glyphs.insert(
+                    character,
+                    Glyph {
+                        page: attribute(&attributes, "page", number)?,
+                        source: source,
+                        offset: Point::new(
+                            attribute(&attributes, "xoffset", number)?,
+                            attribute(&attributes, "yoffset", number)?,
+                        ),
+                        advance: attribute(&attributes, "xadvance", number)?,
+                    },
+                );
+            }
+            "kerning" => {
+                let first: u32 = attribute(&attributes, "first", number)?;
+                let second: u32 = attribute(&attributes, "second", number)?;
+                if let (Some(first), Some(second)) =
+                    (::std::char::from_u32(first), ::std::char::from_u32(second))
+                {
+                    font.set_kerning(first, second, attribute(&attributes, "amount", number)?);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((font, files))
+}
+
+/// Split a line of a BMFont file at whitespace outside of quotes, removing the quotes
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut quoted = false;
+    for character in line.chars() {
+        match character {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(::std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn attribute<T>(
+    attributes: &HashMap<String, String>,
+    key: &str,
+    line: usize,
+) -> Result<T, FontError>
+where
+    T: FromStr,
+{
+    let value = attributes.get(key).ok_or(FontError::Parse {
+        line: line,
+        message: format!("missing {}", key),
+    })?;
+    value.parse().map_err(|_| FontError::Parse {
+        line: line,
+        message: format!("invalid {}: {}", key, value),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::common::arena::Arena;
+    use super::*;
+
+    fn texture() -> TextureHandle {
+        TextureHandle(Arena::new().insert(()))
+    }
+
+    fn grid_font() -> BitmapFont {
+        let sheet = SpriteSheet::new(texture(), (32, 8), (8, 8));
+        BitmapFont::from_grid(&sheet, "ab c")
+    }
+
+    #[test]
+    fn test_grid_font_layout() {
+        let font = grid_font();
+
+        let layout = font.layout("ab\nc", &TextStyle::default());
+
+        assert_eq!((layout.width, layout.height), (16, 16));
+        let positions: Vec<Rect> = layout.glyphs.iter().map(|glyph| glyph.position).collect();
+        assert_eq!(
+            positions,
+            vec![
+                Rect::new(0, 0, 8, 8),
+                Rect::new(8, 0, 8, 8),
+                Rect::new(0, 8, 8, 8),
+            ]
+        );
+        assert_eq!(layout.glyphs[2].source, Rect::new(24, 0, 8, 8));
+    }
+
+    #[test]
+    fn test_wrapping_alignment_and_line_spacing() {
+        let font = grid_font();
+        let style = TextStyle {
+            align: TextAlign::Right,
+            wrap_width: Some(32),
+            line_spacing: 2,
+            ..TextStyle::default()
+        };
+
+        // "ab ab" is 40 pixels wide, the second word moves to the next line
+        assert_eq!(font.measure("ab ab c", &style), (32, 18));
+
+        let layout = font.layout("ab ab c", &style);
+        assert_eq!(layout.glyphs[0].position, Rect::new(16, 0, 8, 8));
+        assert_eq!(layout.glyphs[2].position, Rect::new(0, 10, 8, 8));
+    }
+
+    #[test]
+    fn test_parse_fnt_with_kerning() {
+        let source = r#"info face="Test Font" size=12
+common lineHeight=14 base=11 scaleW=64 scaleH=64 pages=1
+page id=0 file="test font.png"
+chars count=3
+char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=2 xadvance=7 page=0 chnl=15
+char id=86 x=6 y=0 width=6 height=8 xoffset=1 yoffset=2 xadvance=7 page=0 chnl=15
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=4 page=0 chnl=15
+kerning first=65 second=86 amount=-2
+"#;
+
+        let font = BitmapFont::parse_fnt(source, &[texture()]).unwrap();
+
+        assert_eq!(font.get_line_height(), 14);
+        assert_eq!(font.get_kerning('A', 'V'), -2);
+        assert_eq!(font.line_width("AV A"), 7 - 2 + 7 + 4 + 7);
+        let layout = font.layout("AV", &TextStyle::default());
+        assert_eq!(layout.glyphs[1].position, Rect::new(6, 2, 6, 8));
+        assert_eq!(font.get_glyph(' ').unwrap().source, None);
+    }
+
+    #[test]
+    fn test_parse_fnt_errors() {
+        let glyph = "char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=0 xadvance=7 page=1\n";
+
+        match BitmapFont::parse_fnt(glyph, &[texture()]) {
+            Err(FontError::MissingPage(1)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match BitmapFont::parse_fnt("common\nchar id=65 x=0", &[]) {
+            Err(FontError::Parse { line: 1, .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        // Pages are numbered in order from 0
+        let pages = "page id=0 file=\"a.png\"\npage id=2 file=\"c.png\"\n";
+        match BitmapFont::parse_fnt(pages, &[texture(), texture(), texture()]) {
+            Err(FontError::Parse { line: 2, .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match BitmapFont::parse_fnt("page id=0 file=\"a.png\"\npage id=0 file=\"b.png\"", &[]) {
+            Err(FontError::Parse { line: 2, .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let mut font = BitmapFont::new(8);
+        let glyph = Glyph {
+            page: 0,
+            source: Some(Rect::new(0, 0, 6, 8)),
+            offset: Point::new(0, 0),
+            advance: 7,
+        };
+        match font.insert_glyph('A', glyph.clone()) {
+            Err(FontError::MissingPage(0)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(font.get_glyph('A').is_none());
+        font.add_page(texture());
+        assert!(font.insert_glyph('A', glyph).is_ok());
+    }
+}
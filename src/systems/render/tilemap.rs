@@ -0,0 +1,501 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{Rect, SpriteSheet, TextureAsset, TextureAtlas, TextureHandle};
+
+/// Width and height of the chunks of a `TileLayer` in tiles
+/// Chunks are culled as a whole and empty ones are skipped
+pub const CHUNK_SIZE: u32 = 16;
+
+/// A cell of a `TileLayer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    /// Index of the tile in the `Tileset`
+    pub index: u32,
+    /// Mirror the tile horizontally
+    pub flip_h: bool,
+    /// Mirror the tile vertically
+    pub flip_v: bool,
+}
+
+impl Tile {
+    pub fn new(index: u32) -> Tile {
+        Tile {
+            index: index,
+            flip_h: false,
+            flip_v: false,
+        }
+    }
+
+    pub fn flipped(index: u32, flip_h: bool, flip_v: bool) -> Tile {
+        Tile {
+            index: index,
+            flip_h: flip_h,
+            flip_v: flip_v,
+        }
+    }
+}
+
+/// A frame of an animated tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileFrame {
+    /// Index of the tile shown during the frame
+    pub index: u32,
+    pub duration: Duration,
+}
+
+/// The tiles of a texture
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    texture: TextureHandle,
+    tiles: Vec<Rect>,
+    /// Frames of the animated tiles by their index
+    animations: HashMap<u32, Vec<TileFrame>>,
+    /// Keeps the texture loaded, see `set_asset`
+    asset: Option<TextureAsset>,
+}
+
+impl Tileset {
+    pub fn new(texture: TextureHandle) -> Tileset {
+        Tileset {
+            texture: texture,
+            tiles: vec![],
+            animations: HashMap::new(),
+            asset: None,
+        }
+    }
+
+    /// Every frame of `sheet` is a tile, in the order of the frames
+    pub fn from_sprite_sheet(sheet: &SpriteSheet) -> Tileset {
+        let mut tileset = Tileset::new(sheet.get_texture());
+        tileset.tiles = (0..sheet.len())
+            .filter_map(|index| sheet.frame(index))
+            .collect();
+        tileset
+    }
+
+    /// Every frame of `atlas` is a tile, in the order of the frames
+    pub fn from_atlas(atlas: &TextureAtlas) -> Tileset {
+        let mut tileset = Tileset::new(atlas.get_texture());
+        tileset.tiles = atlas
+            .get_frames()
+            .iter()
+            .map(|frame| frame.source)
+            .collect();
+        tileset
+    }
+
+    pub fn get_texture(&self) -> TextureHandle {
+        self.texture
+    }
+
+    /// Draw the texture of `asset`, it stays loaded while the tileset is alive
+    pub fn set_asset(&mut self, asset: TextureAsset) {
+        self.texture = asset.handle();
+        self.asset = Some(asset);
+    }
+
+    /// Add a tile drawing `source` of the texture
+    /// Returns the index of the tile
+    pub fn push(&mut self, source: Rect) -> u32 {
+        self.tiles.push(source);
+        self.tiles.len() as u32 - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Source rectangle of the tile at `index`
+    pub fn get_source(&self, index: u32) -> Option<Rect> {
+        self.tiles.get(index as usize).cloned()
+    }
+
+    /// Animate the tile at `index`, it shows `frames` in a loop instead of itself
+    /// An empty list of frames stops the animation
+    pub fn set_animation(&mut self, index: u32, frames: Vec<TileFrame>) {
+        if frames.is_empty() {
+            self.animations.remove(&index);
+        } else {
+            self.animations.insert(index, frames);
+        }
+    }
+
+    pub fn get_animation(&self, index: u32) -> Option<&[TileFrame]> {
+        self.animations.get(&index).map(|frames| &frames[..])
+    }
+
+    /// Index of the tile shown instead of `index` once `elapsed` passed
+    pub fn animate(&self, index: u32, elapsed: &Duration) -> u32 {
+        let frames = match self.animations.get(&index) {
+            Some(frames) => frames,
+            None => return index,
+        };
+        let total: u128 = frames.iter().map(|frame| frame.duration.as_nanos()).sum();
+        if total == 0 {
+            return frames[0].index;
+        }
+        let mut time = elapsed.as_nanos() % total;
+        for frame in frames.iter() {
+            if time < frame.duration.as_nanos() {
+                return frame.index;
+            }
+            time -= frame.duration.as_nanos();
+        }
+        frames[frames.len() - 1].index
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    tiles: Vec<Option<Tile>>,
+    /// Number of tiles that are not empty
+    count: usize,
+}
+
+/// A grid of tiles, stored in chunks of `CHUNK_SIZE` by `CHUNK_SIZE` tiles
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    pub name: String,
+    pub visible: bool,
+    /// Size in tiles
+    size: (u32, u32),
+    /// Number of chunks in a row
+    columns: u32,
+    chunks: Vec<Chunk>,
+}
+
+impl TileLayer {
+    /// An empty layer of `size` tiles
+    pub fn new(name: &str, size: (u32, u32)) -> TileLayer {
+        let columns = size.0.div_ceil(CHUNK_SIZE);
+        let rows = size.1.div_ceil(CHUNK_SIZE);
+        let chunk = Chunk {
+            tiles: vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+            count: 0,
+        };
+        TileLayer {
+            name: name.to_string(),
+            visible: true,
+            size: size,
+            columns: columns,
+            chunks: vec![chunk; (columns * rows) as usize],
+        }
+    }
+
+    /// Size in tiles
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn get_tile(&self, x: u32, y: u32) -> Option<Tile> {
+        let (chunk, index) = self.locate(x, y)?;
+        self.chunks[chunk].tiles[index]
+    }
+
+    /// Replace the tile at `x`, `y`, `None` empties it
+    /// Tiles outside of the layer are ignored
+    /// Returns the previous tile
+    pub fn set_tile(&mut self, x: u32, y: u32, tile: Option<Tile>) -> Option<Tile> {
+        let (chunk, index) = self.locate(x, y)?;
+        let chunk = &mut self.chunks[chunk];
+        let previous = ::std::mem::replace(&mut chunk.tiles[index], tile);
+        match (previous.is_some(), tile.is_some()) {
+            (false, true) => chunk.count += 1,
+            (true, false) => chunk.count -= 1,
+            _ => {}
+        }
+        previous
+    }
+
+    /// Set every tile of the layer to `tile`
+    pub fn fill(&mut self, tile: Option<Tile>) {
+        for y in 0..self.size.1 {
+            for x in 0..self.size.0 {
+                self.set_tile(x, y, tile);
+            }
+        }
+    }
+
+    /// Number of tiles that are not empty
+    pub fn tile_count(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.count).sum()
+    }
+
+    /// Index of the chunk containing the tile and of the tile inside the chunk
+    fn locate(&self, x: u32, y: u32) -> Option<(usize, usize)> {
+        if x >= self.size.0 || y >= self.size.1 {
+            return None;
+        }
+        let chunk = (y / CHUNK_SIZE) * self.columns + x / CHUNK_SIZE;
+        let index = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+        Some((chunk as usize, index as usize))
+    }
+}
+
+/// A tile placed by `Tilemap::visible_tiles`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedTile {
+    pub source: Rect,
+    /// Position relative to the top left corner of the tilemap
+    pub position: Rect,
+    pub flip_h: bool,
+    pub flip_v: bool,
+}
+
+/// Layers of tiles drawn with a `Tileset`, see `RenderComponentInner::set_tilemap`
+#[derive(Debug, Clone)]
+pub struct Tilemap {
+    tileset: Tileset,
+    /// Size in tiles
+    size: (u32, u32),
+    /// Size of a tile in pixels
+    tile_size: (u32, u32),
+    layers: Vec<TileLayer>,
+    /// Time the tiles were animated for
+    elapsed: Duration,
+}
+
+impl Tilemap {
+    /// A tilemap of `size` tiles without layers
+    /// Panics if the tiles are empty
+    pub fn new(tileset: Tileset, size: (u32, u32), tile_size: (u32, u32)) -> Tilemap {
+        assert!(
+            tile_size.0 > 0 && tile_size.1 > 0,
+            "Tiles have to be at least a pixel wide and high"
+        );
+        Tilemap {
+            tileset: tileset,
+            size: size,
+            tile_size: tile_size,
+            layers: vec![],
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    pub fn get_tileset(&self) -> &Tileset {
+        &self.tileset
+    }
+
+    pub fn get_tileset_mut(&mut self) -> &mut Tileset {
+        &mut self.tileset
+    }
+
+    /// Size in tiles
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Size of a tile in pixels
+    pub fn get_tile_size(&self) -> (u32, u32) {
+        self.tile_size
+    }
+
+    /// Size in pixels
+    pub fn pixel_size(&self) -> (u32, u32) {
+        (
+            self.size.0 * self.tile_size.0,
+            self.size.1 * self.tile_size.1,
+        )
+    }
+
+    /// Add an empty layer drawn on top of the others
+    /// Returns the index of the layer
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        self.layers.push(TileLayer::new(name, self.size));
+        self.layers.len() - 1
+    }
+
+    pub fn get_layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+
+    pub fn get_layer(&self, index: usize) -> Option<&TileLayer> {
+        self.layers.get(index)
+    }
+
+    pub fn get_layer_mut(&mut self, index: usize) -> Option<&mut TileLayer> {
+        self.layers.get_mut(index)
+    }
+
+    /// Index of the first layer named `name`
+    pub fn find_layer(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    pub fn get_tile(&self, layer: usize, x: u32, y: u32) -> Option<Tile> {
+        self.layers.get(layer)?.get_tile(x, y)
+    }
+
+    /// Replace a tile of `layer`, see `TileLayer::set_tile`
+    pub fn set_tile(&mut self, layer: usize, x: u32, y: u32, tile: Option<Tile>) -> Option<Tile> {
+        self.layers.get_mut(layer)?.set_tile(x, y, tile)
+    }
+
+    /// Tile containing the point `x`, `y` relative to the top left corner of the tilemap
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<(u32, u32)> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let (x, y) = (x as u32 / self.tile_size.0, y as u32 / self.tile_size.1);
+        if x < self.size.0 && y < self.size.1 {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Advance the animated tiles
+    /// The engine calls it on every fixed update for the tilemaps of render components
+    pub fn update(&mut self, delta_time: &Duration) {
+        self.elapsed += *delta_time;
+    }
+
+    /// The tiles of the visible layers in the chunks overlapping `area`, in drawing order
+    /// `area` is relative to the top left corner of the tilemap
+    pub fn visible_tiles(&self, area: &Rect) -> Vec<PlacedTile> {
+        let (width, height) = self.tile_size;
+        let chunk_width = (CHUNK_SIZE * width) as i32;
+        let chunk_height = (CHUNK_SIZE * height) as i32;
+        let range = |from: i32, to: i32, size: i32, count: u32| {
+            let first = (from.max(0) / size) as u32;
+            let last = if to <= 0 {
+                0
+            } else {
+                (((to - 1) / size) as u32 + 1).min(count)
+            };
+            first..last
+        };
+        let columns = self.size.0.div_ceil(CHUNK_SIZE);
+        let rows = self.size.1.div_ceil(CHUNK_SIZE);
+        let chunk_columns = range(area.left(), area.right(), chunk_width, columns);
+        let chunk_rows = range(area.top(), area.bottom(), chunk_height, rows);
+        let mut placed = vec![];
+        for layer in self.layers.iter().filter(|layer| layer.visible) {
+            for row in chunk_rows.clone() {
+                for column in chunk_columns.clone() {
+                    let chunk = &layer.chunks[(row * columns + column) as usize];
+                    if chunk.count == 0 {
+                        continue;
+                    }
+                    for (index, tile) in chunk.tiles.iter().enumerate() {
+                        let tile = match *tile {
+                            Some(tile) => tile,
+                            None => continue,
+                        };
+                        let index = index as u32;
+                        let x = column * CHUNK_SIZE + index % CHUNK_SIZE;
+                        let y = row * CHUNK_SIZE + index / CHUNK_SIZE;
+                        let shown = self.tileset.animate(tile.index, &self.elapsed);
+                        if let Some(source) = self.tileset.get_source(shown) {
+                            placed.push(PlacedTile {
+                                source: source,
+                                position: Rect::new(
+                                    (x * width) as i32,
+                                    (y * height) as i32,
+                                    width,
+                                    height,
+                                ),
+                                flip_h: tile.flip_h,
+                                flip_v: tile.flip_v,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        placed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::common::arena::Arena;
+    use super::*;
+
+    fn tilemap(size: (u32, u32)) -> Tilemap {
+        let texture = TextureHandle(Arena::new().insert(()));
+        let tileset = Tileset::from_sprite_sheet(&SpriteSheet::new(texture, (32, 8), (8, 8)));
+        Tilemap::new(tileset, size, (8, 8))
+    }
+
+    #[test]
+    fn test_set_tiles_across_chunks() {
+        let mut map = tilemap((40, 20));
+        let layer = map.add_layer("ground");
+
+        assert_eq!(map.set_tile(layer, 39, 19, Some(Tile::new(1))), None);
+        assert_eq!(
+            map.set_tile(layer, 39, 19, Some(Tile::flipped(2, true, false))),
+            Some(Tile::new(1))
+        );
+        assert_eq!(map.set_tile(layer, 40, 0, Some(Tile::new(1))), None);
+        assert_eq!(map.get_tile(layer, 39, 19).unwrap().index, 2);
+        assert_eq!(map.get_layer(layer).unwrap().tile_count(), 1);
+        map.set_tile(layer, 39, 19, None);
+        assert_eq!(map.get_layer(layer).unwrap().tile_count(), 0);
+        assert_eq!(map.tile_at(317, 20), Some((39, 2)));
+        assert_eq!(map.tile_at(320, 20), None);
+    }
+
+    #[test]
+    fn test_only_chunks_overlapping_the_area_are_visible() {
+        let mut map = tilemap((64, 64));
+        let ground = map.add_layer("ground");
+        let top = map.add_layer("top");
+        map.get_layer_mut(ground).unwrap().fill(Some(Tile::new(0)));
+        map.set_tile(top, 0, 0, Some(Tile::flipped(3, false, true)));
+
+        let tiles = map.visible_tiles(&Rect::new(10, 10, 20, 20));
+
+        let chunk = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+        assert_eq!(tiles.len(), chunk + 1);
+        assert_eq!(tiles[chunk].source, Rect::new(24, 0, 8, 8));
+        assert!(tiles[chunk].flip_v);
+        // The area touches four chunks of each layer
+        assert_eq!(
+            map.visible_tiles(&Rect::new(120, 120, 16, 16)).len(),
+            4 * chunk + 1
+        );
+        assert!(map.visible_tiles(&Rect::new(-50, 0, 40, 40)).is_empty());
+
+        map.get_layer_mut(ground).unwrap().visible = false;
+        assert_eq!(map.visible_tiles(&Rect::new(0, 0, 512, 512)).len(), 1);
+    }
+
+    #[test]
+    fn test_animated_tiles_loop() {
+        let mut map = tilemap((1, 1));
+        let layer = map.add_layer("water");
+        map.set_tile(layer, 0, 0, Some(Tile::new(0)));
+        let frame = |index: u32| TileFrame {
+            index: index,
+            duration: Duration::from_millis(100),
+        };
+        map.get_tileset_mut()
+            .set_animation(0, vec![frame(1), frame(2)]);
+        let shown = |map: &Tilemap| map.visible_tiles(&Rect::new(0, 0, 8, 8))[0].source.x();
+
+        assert_eq!(shown(&map), 8);
+        map.update(&Duration::from_millis(150));
+        assert_eq!(shown(&map), 16);
+        map.update(&Duration::from_millis(100));
+        assert_eq!(shown(&map), 8);
+        // Long running animations do not lose precision
+        map.update(&Duration::from_secs(10_000_000));
+        assert_eq!(shown(&map), 8);
+        map.update(&Duration::from_millis(50));
+        assert_eq!(shown(&map), 16);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tiles_can_not_be_empty() {
+        let texture = TextureHandle(Arena::new().insert(()));
+        Tilemap::new(Tileset::new(texture), (1, 1), (8, 0));
+    }
+}
@@ -0,0 +1,458 @@
+use serde_json;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use super::super::super::common::vector2::Vector2;
+use super::super::render::TileFrame;
+use super::{
+    decode_tiles, external_tileset, resolve, ObjectLayer, ObjectShape, Properties, PropertyValue,
+    TiledError, TiledLayer, TiledMap, TiledObject, TiledTileLayer, TiledTileset,
+};
+
+/// Parse a map in Tiled's JSON format, files are relative to `directory`
+pub fn parse_map(source: &str, directory: Option<&Path>) -> Result<TiledMap, TiledError> {
+    let file: layout::Map = serde_json::from_str(source)?;
+    if file.orientation != "orthogonal" {
+        return Err(TiledError::Unsupported(format!(
+            "{} maps",
+            file.orientation
+        )));
+    }
+    if file.infinite {
+        return Err(TiledError::Unsupported("Infinite maps".to_string()));
+    }
+    let tilesets = file
+        .tilesets
+        .iter()
+        .map(|tileset| match tileset.source {
+            Some(ref source) => external_tileset(source, tileset.first_gid, directory),
+            None => convert_tileset(tileset, tileset.first_gid, directory),
+        })
+        .collect::<Result<Vec<TiledTileset>, TiledError>>()?;
+    let layers = file
+        .layers
+        .iter()
+        .map(|layer| convert_layer(layer, (file.width, file.height)))
+        .collect::<Result<Vec<TiledLayer>, TiledError>>()?;
+    Ok(TiledMap {
+        size: (file.width, file.height),
+        tile_size: (file.tile_width, file.tile_height),
+        properties: convert_properties(&file.properties)?,
+        tilesets: tilesets,
+        layers: layers,
+    })
+}
+
+/// Parse a tileset in Tiled's JSON format, its image is relative to `directory`
+pub fn parse_tileset(
+    source: &str,
+    first_gid: u32,
+    directory: Option<&Path>,
+) -> Result<TiledTileset, TiledError> {
+    let tileset: layout::Tileset = serde_json::from_str(source)?;
+    convert_tileset(&tileset, first_gid, directory)
+}
+
+fn convert_tileset(
+    tileset: &layout::Tileset,
+    first_gid: u32,
+    directory: Option<&Path>,
+) -> Result<TiledTileset, TiledError> {
+    let image = match tileset.image {
+        Some(ref image) => image,
+        None => {
+            return Err(TiledError::Unsupported(format!(
+                "Image collection tileset {}",
+                tileset.name
+            )))
+        }
+    };
+    let mut animations = HashMap::new();
+    let mut tile_properties = HashMap::new();
+    for tile in tileset.tiles.iter() {
+        if !tile.animation.is_empty() {
+            let frames = tile
+                .animation
+                .iter()
+                .map(|frame| TileFrame {
+                    index: frame.tile_id,
+                    duration: Duration::from_millis(frame.duration),
+                })
+                .collect();
+            animations.insert(tile.id, frames);
+        }
+        if !tile.properties.is_empty() {
+            tile_properties.insert(tile.id, convert_properties(&tile.properties)?);
+        }
+    }
+    Ok(TiledTileset {
+        first_gid: first_gid,
+        name: tileset.name.clone(),
+        image: resolve(image, directory),
+        image_size: (tileset.image_width, tileset.image_height),
+        tile_size: (tileset.tile_width, tileset.tile_height),
+        margin: tileset.margin,
+        spacing: tileset.spacing,
+        tile_count: tileset.tile_count,
+        animations: animations,
+        tile_properties: tile_properties,
+        properties: convert_properties(&tileset.properties)?,
+    })
+}
+
+fn convert_layer(layer: &layout::Layer, size: (u32, u32)) -> Result<TiledLayer, TiledError> {
+    let properties = convert_properties(&layer.properties)?;
+    match layer.kind.as_str() {
+        "tilelayer" => {
+            let data = match layer.data {
+                Some(layout::Data::Tiles(ref tiles)) => tiles.clone(),
+                Some(layout::Data::Encoded(ref data)) => decode_tiles(
+                    data,
+                    Some(layer.encoding.as_str()),
+                    layer.compression.as_deref(),
+                )?,
+                None => {
+                    return Err(TiledError::Invalid(format!(
+                        "Layer {} has no data",
+                        layer.name
+                    )))
+                }
+            };
+            if data.len() != (size.0 * size.1) as usize {
+                return Err(TiledError::Invalid(format!(
+                    "Layer {} is not the size of the map",
+                    layer.name
+                )));
+            }
+            Ok(TiledLayer::Tiles(TiledTileLayer {
+                name: layer.name.clone(),
+                visible: layer.visible,
+                properties: properties,
+                data: data,
+            }))
+        }
+        "objectgroup" => Ok(TiledLayer::Objects(ObjectLayer {
+            name: layer.name.clone(),
+            visible: layer.visible,
+            properties: properties,
+            objects: layer
+                .objects
+                .iter()
+                .map(convert_object)
+                .collect::<Result<Vec<TiledObject>, TiledError>>()?,
+        })),
+        kind => Err(TiledError::Unsupported(format!(
+            "Layers of type {}, like {}",
+            kind, layer.name
+        ))),
+    }
+}
+
+fn convert_object(object: &layout::Object) -> Result<TiledObject, TiledError> {
+    let points = |points: &[layout::Point]| {
+        points
+            .iter()
+            .map(|point| Vector2::new(point.x, point.y))
+            .collect()
+    };
+    let shape = if object.text.is_some() || object.gid.is_some() {
+        return Err(TiledError::Unsupported(format!(
+            "Text and tile objects, like object {}",
+            object.id
+        )));
+    } else if object.ellipse {
+        ObjectShape::Ellipse
+    } else if object.point {
+        ObjectShape::Point
+    } else if let Some(ref polygon) = object.polygon {
+        ObjectShape::Polygon(points(polygon))
+    } else if let Some(ref polyline) = object.polyline {
+        ObjectShape::Polyline(points(polyline))
+    } else {
+        ObjectShape::Rectangle
+    };
+    Ok(TiledObject {
+        id: object.id,
+        name: object.name.clone(),
+        class: object.class.clone().unwrap_or_else(|| object.kind.clone()),
+        position: Vector2::new(object.x, object.y),
+        width: object.width,
+        height: object.height,
+        rotation: object.rotation,
+        visible: object.visible,
+        shape: shape,
+        properties: convert_properties(&object.properties)?,
+    })
+}
+
+fn convert_properties(properties: &[layout::Property]) -> Result<Properties, TiledError> {
+    properties
+        .iter()
+        .map(|property| {
+            let value = match property.value {
+                Value::String(ref value) => value.clone(),
+                ref value => value.to_string(),
+            };
+            let value = PropertyValue::parse(&property.kind, &value)?;
+            Ok((property.name.clone(), value))
+        })
+        .collect()
+}
+
+/// Layout of the map and tileset files
+mod layout {
+    use serde_json::Value;
+
+    #[derive(Deserialize)]
+    pub struct Map {
+        #[serde(default = "orthogonal")]
+        pub orientation: String,
+        #[serde(default)]
+        pub infinite: bool,
+        pub width: u32,
+        pub height: u32,
+        #[serde(rename = "tilewidth")]
+        pub tile_width: u32,
+        #[serde(rename = "tileheight")]
+        pub tile_height: u32,
+        #[serde(default)]
+        pub layers: Vec<Layer>,
+        #[serde(default)]
+        pub tilesets: Vec<Tileset>,
+        #[serde(default)]
+        pub properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Tileset {
+        #[serde(rename = "firstgid", default)]
+        pub first_gid: u32,
+        pub source: Option<String>,
+        #[serde(default)]
+        pub name: String,
+        pub image: Option<String>,
+        #[serde(rename = "imagewidth", default)]
+        pub image_width: u32,
+        #[serde(rename = "imageheight", default)]
+        pub image_height: u32,
+        #[serde(rename = "tilewidth", default)]
+        pub tile_width: u32,
+        #[serde(rename = "tileheight", default)]
+        pub tile_height: u32,
+        #[serde(default)]
+        pub margin: u32,
+        #[serde(default)]
+        pub spacing: u32,
+        #[serde(rename = "tilecount", default)]
+        pub tile_count: u32,
+        #[serde(default)]
+        pub tiles: Vec<TileEntry>,
+        #[serde(default)]
+        pub properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct TileEntry {
+        pub id: u32,
+        #[serde(default)]
+        pub animation: Vec<Frame>,
+        #[serde(default)]
+        pub properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Frame {
+        #[serde(rename = "tileid")]
+        pub tile_id: u32,
+        /// Milliseconds
+        pub duration: u64,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Layer {
+        #[serde(rename = "type")]
+        pub kind: String,
+        #[serde(default)]
+        pub name: String,
+        #[serde(default = "visible")]
+        pub visible: bool,
+        pub data: Option<Data>,
+        #[serde(default = "csv")]
+        pub encoding: String,
+        pub compression: Option<String>,
+        #[serde(default)]
+        pub objects: Vec<Object>,
+        #[serde(default)]
+        pub properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum Data {
+        Tiles(Vec<u32>),
+        /// Base64, see the layer's encoding and compression
+        Encoded(String),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Object {
+        #[serde(default)]
+        pub id: u32,
+        #[serde(default)]
+        pub name: String,
+        #[serde(rename = "type", default)]
+        pub kind: String,
+        pub class: Option<String>,
+        #[serde(default)]
+        pub x: f32,
+        #[serde(default)]
+        pub y: f32,
+        #[serde(default)]
+        pub width: f32,
+        #[serde(default)]
+        pub height: f32,
+        #[serde(default)]
+        pub rotation: f32,
+        #[serde(default = "visible")]
+        pub visible: bool,
+        #[serde(default)]
+        pub ellipse: bool,
+        #[serde(default)]
+        pub point: bool,
+        pub polygon: Option<Vec<Point>>,
+        pub polyline: Option<Vec<Point>>,
+        pub text: Option<Value>,
+        pub gid: Option<u32>,
+        #[serde(default)]
+        pub properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Point {
+        pub x: f32,
+        pub y: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Property {
+        pub name: String,
+        #[serde(rename = "type", default = "string")]
+        pub kind: String,
+        pub value: Value,
+    }
+
+    fn orthogonal() -> String {
+        "orthogonal".to_string()
+    }
+
+    fn visible() -> bool {
+        true
+    }
+
+    fn csv() -> String {
+        "csv".to_string()
+    }
+
+    fn string() -> String {
+        "string".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAP: &str = r#"{
+        "orientation": "orthogonal", "infinite": false,
+        "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8,
+        "properties": [
+            {"name": "level", "type": "int", "value": 3},
+            {"name": "boss", "type": "object", "value": 7}
+        ],
+        "tilesets": [{
+            "firstgid": 1, "name": "terrain", "image": "terrain.png",
+            "imagewidth": 32, "imageheight": 8, "tilewidth": 8, "tileheight": 8,
+            "tilecount": 4, "columns": 4,
+            "tiles": [{"id": 0, "animation": [
+                {"tileid": 0, "duration": 50}, {"tileid": 3, "duration": 50}
+            ]}]
+        }],
+        "layers": [
+            {"type": "tilelayer", "name": "ground", "width": 2, "height": 2,
+             "data": [1, 0, 1073741826, 4]},
+            {"type": "tilelayer", "name": "top", "width": 2, "height": 2, "visible": false,
+             "encoding": "base64", "data": "AQAAAAAAAAACAACAAwAAAA=="},
+            {"type": "objectgroup", "name": "walls", "objects": [
+                {"id": 1, "name": "floor", "type": "wall", "x": 0, "y": 8,
+                 "width": 16, "height": 8, "properties": [
+                    {"name": "trigger", "type": "bool", "value": true}
+                 ]},
+                {"id": 2, "class": "path", "x": 1, "y": 1,
+                 "polyline": [{"x": 0, "y": 0}, {"x": 5, "y": 5}]}
+            ]}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_map() {
+        let map = TiledMap::parse_json(MAP).unwrap();
+
+        assert_eq!((map.size, map.tile_size), ((2, 2), (8, 8)));
+        assert_eq!(map.properties["level"], PropertyValue::Int(3));
+        assert_eq!(map.properties["boss"], PropertyValue::Object(7));
+        let tileset = &map.tilesets[0];
+        assert_eq!((tileset.first_gid, tileset.tile_count), (1, 4));
+        assert_eq!(tileset.animations[&0][1].index, 3);
+
+        match (map.get_layer("ground"), map.get_layer("top")) {
+            (Some(&TiledLayer::Tiles(ref ground)), Some(&TiledLayer::Tiles(ref top))) => {
+                assert_eq!(ground.data, vec![1, 0, 0x4000_0002, 4]);
+                assert_eq!(top.data, vec![1, 0, 0x8000_0002, 3]);
+                assert!(!top.visible);
+            }
+            _ => panic!("Expected two tile layers"),
+        }
+
+        let objects = map.objects();
+        assert_eq!(objects[0].class, "wall");
+        assert!(objects[0].is_trigger());
+        assert_eq!(objects[1].class, "path");
+        assert_eq!(
+            objects[1].shape,
+            ObjectShape::Polyline(vec![Vector2::new(0., 0.), Vector2::new(5., 5.)])
+        );
+    }
+
+    #[test]
+    fn test_unsupported_features_are_reported() {
+        let unsupported = |map: &str| match TiledMap::parse_json(map) {
+            Err(TiledError::Unsupported(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        };
+        let map = |extra: &str| {
+            format!(
+                r#"{{"width": 1, "height": 1, "tilewidth": 8, "tileheight": 8, {}}}"#,
+                extra
+            )
+        };
+
+        unsupported(&map(r#""orientation": "hexagonal""#));
+        unsupported(&map(r#""layers": [{"type": "group", "name": "props"}]"#));
+        unsupported(&map(
+            r#""tilesets": [{"firstgid": 1, "source": "terrain.tsj"}]"#,
+        ));
+        unsupported(&map(
+            r#""layers": [{"type": "tilelayer", "encoding": "base64", "compression": "zstd",
+                "data": "AQAAAA=="}]"#,
+        ));
+        unsupported(&map(
+            r#""properties": [{"name": "stats", "type": "class", "value": {}}]"#,
+        ));
+        match TiledMap::parse_json(&map(r#""layers": [{"type": "tilelayer", "data": [1, 2]}]"#)) {
+            Err(TiledError::Invalid(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,744 @@
+use flate2::read::{GzDecoder, ZlibDecoder};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::super::common::aabb::AABB;
+use super::super::common::circle::Circle;
+use super::super::common::obb::OBB2D;
+use super::super::common::vector2::Vector2;
+use super::super::components::position::Position;
+use super::super::ecs::{Entity, World};
+use super::collision::{Collider, Trigger};
+use super::render::{
+    AssetError, Color, RenderSystem, SpriteSheet, TextureHandle, Tile, TileFrame, Tilemap, Tileset,
+};
+
+mod json;
+mod tmx;
+
+/// Flags of a global tile id
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+const FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL;
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(io::Error),
+    Json(::serde_json::Error),
+    Xml(::xml::reader::Error),
+    Asset(AssetError),
+    /// The map uses a feature of Tiled the importer does not support
+    Unsupported(String),
+    /// The map is malformed
+    Invalid(String),
+}
+
+impl From<io::Error> for TiledError {
+    fn from(error: io::Error) -> Self {
+        TiledError::Io(error)
+    }
+}
+
+impl From<::serde_json::Error> for TiledError {
+    fn from(error: ::serde_json::Error) -> Self {
+        TiledError::Json(error)
+    }
+}
+
+impl From<::xml::reader::Error> for TiledError {
+    fn from(error: ::xml::reader::Error) -> Self {
+        TiledError::Xml(error)
+    }
+}
+
+impl From<AssetError> for TiledError {
+    fn from(error: AssetError) -> Self {
+        TiledError::Asset(error)
+    }
+}
+
+/// Value of a custom property
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Color),
+    /// Path of a file, relative to the file defining the property
+    File(String),
+    /// Id of an object
+    Object(u32),
+}
+
+impl PropertyValue {
+    /// Parse a property of Tiled's type `kind` written as `value`
+    pub fn parse(kind: &str, value: &str) -> Result<PropertyValue, TiledError> {
+        let invalid = || TiledError::Invalid(format!("Invalid {} property {:?}", kind, value));
+        Ok(match kind {
+            "bool" => PropertyValue::Bool(value.parse().map_err(|_| invalid())?),
+            "int" => PropertyValue::Int(value.parse().map_err(|_| invalid())?),
+            "float" => PropertyValue::Float(value.parse().map_err(|_| invalid())?),
+            "string" => PropertyValue::String(value.to_string()),
+            "color" => PropertyValue::Color(parse_color(value).ok_or_else(invalid)?),
+            "file" => PropertyValue::File(value.to_string()),
+            "object" => PropertyValue::Object(value.parse().map_err(|_| invalid())?),
+            _ => {
+                return Err(TiledError::Unsupported(format!(
+                    "Properties of type {}",
+                    kind
+                )))
+            }
+        })
+    }
+}
+
+/// Custom properties by their name
+pub type Properties = HashMap<String, PropertyValue>;
+
+/// A tileset of a single image
+#[derive(Debug, Clone)]
+pub struct TiledTileset {
+    /// Global id of the first tile
+    pub first_gid: u32,
+    pub name: String,
+    /// Path of the image, relative to the map when parsed from a string
+    pub image: PathBuf,
+    pub image_size: (u32, u32),
+    pub tile_size: (u32, u32),
+    /// Pixels between the edge of the image and the first tile
+    pub margin: u32,
+    /// Pixels between two neighbouring tiles
+    pub spacing: u32,
+    pub tile_count: u32,
+    /// Frames of the animated tiles by the index of the tile in the tileset
+    pub animations: HashMap<u32, Vec<TileFrame>>,
+    /// Custom properties of the tiles by their index in the tileset
+    pub tile_properties: HashMap<u32, Properties>,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledTileLayer {
+    pub name: String,
+    pub visible: bool,
+    pub properties: Properties,
+    /// Global tile ids including the flip flags, row by row, 0 is an empty tile
+    pub data: Vec<u32>,
+}
+
+/// Outline of an object
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectShape {
+    Rectangle,
+    /// Ellipse inside the object's rectangle
+    Ellipse,
+    Point,
+    /// Corners relative to the object's position
+    Polygon(Vec<Vector2>),
+    /// Points relative to the object's position
+    Polyline(Vec<Vector2>),
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    /// Class of the object, its type in maps saved by Tiled before 1.9
+    pub class: String,
+    /// Top left corner of the object in pixels, relative to the map
+    pub position: Vector2,
+    pub width: f32,
+    pub height: f32,
+    /// Rotation in degrees around `position`, clockwise
+    pub rotation: f32,
+    pub visible: bool,
+    pub shape: ObjectShape,
+    pub properties: Properties,
+}
+
+impl TiledObject {
+    /// The collider of the object and its center relative to the map
+    /// Polygons are centered on the average of their corners and have to be convex, points and
+    /// polylines have no collider
+    pub fn collider(&self) -> Result<Option<(Vector2, Collider)>, TiledError> {
+        let half = Vector2::new(0.5 * self.width, 0.5 * self.height);
+        let center = self.position.add(&half.rotate(self.rotation));
+        let origin = Vector2::new(0., 0.);
+        Ok(Some(match self.shape {
+            ObjectShape::Rectangle if self.rotation == 0. => (
+                center,
+                Collider::AABB(AABB::new(origin, self.width, self.height)),
+            ),
+            ObjectShape::Rectangle => {
+                let local = [
+                    Vector2::new(1., 0.).rotate(self.rotation),
+                    Vector2::new(0., 1.).rotate(self.rotation),
+                ];
+                (center, Collider::OBB(OBB2D::new(origin, local, half)))
+            }
+            ObjectShape::Ellipse if (self.width - self.height).abs() < 1e-3 => (
+                center,
+                Collider::Circle(Circle::new(origin, 0.5 * self.width)),
+            ),
+            ObjectShape::Ellipse => {
+                return Err(TiledError::Unsupported(format!(
+                    "Colliders of ellipses, object {} is not a circle",
+                    self.id
+                )))
+            }
+            ObjectShape::Polygon(ref points) => {
+                if points.len() < 3 {
+                    return Err(TiledError::Invalid(format!(
+                        "Polygon of object {} has less than 3 corners",
+                        self.id
+                    )));
+                }
+                let corners: Vec<Vector2> = points
+                    .iter()
+                    .map(|point| self.position.add(&point.rotate(self.rotation)))
+                    .collect();
+                let mut center = Vector2::new(0., 0.);
+                for corner in corners.iter() {
+                    center.add_mut(corner);
+                }
+                let center = (1. / corners.len() as f32) * center;
+                let corners = corners.iter().map(|corner| corner.sub(&center)).collect();
+                match Collider::polygon(corners) {
+                    Some(collider) => (center, collider),
+                    None => {
+                        return Err(TiledError::Unsupported(format!(
+                            "Colliders of concave or crossed polygons, object {} is one",
+                            self.id
+                        )))
+                    }
+                }
+            }
+            ObjectShape::Point | ObjectShape::Polyline(_) => return Ok(None),
+        }))
+    }
+
+    /// The object's `trigger` property is set to `true`
+    pub fn is_trigger(&self) -> bool {
+        self.properties.get("trigger") == Some(&PropertyValue::Bool(true))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub properties: Properties,
+    pub objects: Vec<TiledObject>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TiledLayer {
+    Tiles(TiledTileLayer),
+    Objects(ObjectLayer),
+}
+
+impl TiledLayer {
+    pub fn get_name(&self) -> &str {
+        match *self {
+            TiledLayer::Tiles(ref layer) => &layer.name,
+            TiledLayer::Objects(ref layer) => &layer.name,
+        }
+    }
+}
+
+/// An orthogonal map made with the Tiled editor
+/// Parse it from TMX or JSON, then draw it with `create_tilemap` and add its colliders to the
+/// world with `spawn_colliders`
+/// Infinite maps, group and image layers, image collection tilesets, diagonally flipped tiles
+/// and text and tile objects are not supported
+#[derive(Debug, Clone)]
+pub struct TiledMap {
+    /// Size in tiles
+    pub size: (u32, u32),
+    /// Size of a tile in pixels
+    pub tile_size: (u32, u32),
+    pub properties: Properties,
+    pub tilesets: Vec<TiledTileset>,
+    /// Layers from the bottom to the top
+    pub layers: Vec<TiledLayer>,
+}
+
+impl TiledMap {
+    /// Parse a map in Tiled's JSON format
+    /// External tilesets are only supported by `load`
+    pub fn parse_json(source: &str) -> Result<TiledMap, TiledError> {
+        json::parse_map(source, None)
+    }
+
+    /// Parse a map in Tiled's TMX format
+    /// External tilesets are only supported by `load`
+    pub fn parse_tmx(source: &str) -> Result<TiledMap, TiledError> {
+        tmx::parse_map(source, None)
+    }
+
+    /// Load a `.tmx`, `.tmj` or `.json` map
+    /// External tilesets and the paths of images are resolved relative to the files
+    /// referencing them
+    pub fn load<P>(path: P) -> Result<TiledMap, TiledError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        match extension(path).as_deref() {
+            Some("tmx") => tmx::parse_map(&source, Some(directory)),
+            Some("tmj") | Some("json") => json::parse_map(&source, Some(directory)),
+            _ => Err(TiledError::Unsupported(format!(
+                "Map files like {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// The first layer named `name`
+    pub fn get_layer(&self, name: &str) -> Option<&TiledLayer> {
+        self.layers.iter().find(|layer| layer.get_name() == name)
+    }
+
+    /// The objects of every object layer
+    pub fn objects(&self) -> Vec<&TiledObject> {
+        self.layers
+            .iter()
+            .filter_map(|layer| match *layer {
+                TiledLayer::Objects(ref layer) => Some(layer.objects.iter()),
+                TiledLayer::Tiles(_) => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Convert the tile layers to a `Tilemap` drawing `texture`, the image of the map's tileset
+    /// Only maps using a single tileset whose tiles are the size of the map's grid are
+    /// supported
+    pub fn to_tilemap(&self, texture: TextureHandle) -> Result<Tilemap, TiledError> {
+        if self.tilesets.len() > 1 {
+            return Err(TiledError::Unsupported(
+                "Maps with more than one tileset".to_string(),
+            ));
+        }
+        if self.tile_size.0 == 0 || self.tile_size.1 == 0 {
+            return Err(TiledError::Invalid(
+                "Tiles of the map's grid are empty".to_string(),
+            ));
+        }
+        let mut tileset = Tileset::new(texture);
+        let mut first_gid = 1;
+        let mut tile_count = 0;
+        if let Some(tiled) = self.tilesets.first() {
+            if tiled.tile_size != self.tile_size {
+                return Err(TiledError::Unsupported(format!(
+                    "Tiles of {} are not the size of the map's grid",
+                    tiled.name
+                )));
+            }
+            let sheet = SpriteSheet::with_spacing(
+                texture,
+                tiled.image_size,
+                tiled.tile_size,
+                tiled.margin,
+                tiled.spacing,
+            );
+            tileset = Tileset::from_sprite_sheet(&sheet);
+            for (index, frames) in tiled.animations.iter() {
+                tileset.set_animation(*index, frames.clone());
+            }
+            first_gid = tiled.first_gid;
+            tile_count = tiled.tile_count;
+        }
+        let mut tilemap = Tilemap::new(tileset, self.size, self.tile_size);
+        for layer in self.layers.iter() {
+            let layer = match *layer {
+                TiledLayer::Tiles(ref layer) => layer,
+                TiledLayer::Objects(_) => continue,
+            };
+            let index = tilemap.add_layer(&layer.name);
+            tilemap.get_layer_mut(index).unwrap().visible = layer.visible;
+            for (i, gid) in layer.data.iter().enumerate() {
+                if *gid == 0 {
+                    continue;
+                }
+                if gid & FLIPPED_DIAGONALLY != 0 {
+                    return Err(TiledError::Unsupported(
+                        "Diagonally flipped or rotated tiles".to_string(),
+                    ));
+                }
+                let id = gid & !FLAGS;
+                if id < first_gid || id - first_gid >= tile_count {
+                    return Err(TiledError::Invalid(format!(
+                        "Layer {} references tile {} outside of its tileset",
+                        layer.name, id
+                    )));
+                }
+                let tile = Tile::flipped(
+                    id - first_gid,
+                    gid & FLIPPED_HORIZONTALLY != 0,
+                    gid & FLIPPED_VERTICALLY != 0,
+                );
+                let (x, y) = (i as u32 % self.size.0, i as u32 / self.size.0);
+                tilemap.set_tile(index, x, y, Some(tile));
+            }
+        }
+        Ok(tilemap)
+    }
+
+    /// Load the image of the map's tileset and convert the tile layers to a `Tilemap`
+    /// The image stays loaded while the tilemap is alive, see `to_tilemap`
+    pub fn create_tilemap(&self, render_system: &mut RenderSystem) -> Result<Tilemap, TiledError> {
+        let tileset = self
+            .tilesets
+            .first()
+            .ok_or_else(|| TiledError::Invalid("The map has no tileset".to_string()))?;
+        let asset = render_system.load_texture(&tileset.image)?;
+        let mut tilemap = self.to_tilemap(asset.handle())?;
+        tilemap.get_tileset_mut().set_asset(asset);
+        Ok(tilemap)
+    }
+
+    /// Create an entity with a `Position` and a `Collider` for every rectangle, circle and
+    /// polygon of the object layers, `origin` is where the map's top left corner is in the world
+    /// Objects with a `trigger` property set to `true` are `Trigger`s
+    /// Hidden layers and objects are included, collision layers are often hidden in the editor
+    /// Returns the entities in the order of the objects
+    pub fn spawn_colliders(
+        &self,
+        world: &mut World,
+        origin: &Vector2,
+    ) -> Result<Vec<Entity>, TiledError> {
+        // Convert every object first, so nothing is spawned for invalid maps
+        let mut colliders = vec![];
+        for object in self.objects() {
+            if let Some((center, collider)) = object.collider()? {
+                colliders.push((center.add(origin), collider, object.is_trigger()));
+            }
+        }
+        Ok(colliders
+            .into_iter()
+            .map(|(center, collider, trigger)| {
+                let entity = world.create_entity();
+                world.add_component(entity, Position(center));
+                world.add_component(entity, collider);
+                if trigger {
+                    world.add_component(entity, Trigger);
+                }
+                entity
+            })
+            .collect())
+    }
+}
+
+/// Load an external tileset in the TSX or JSON format
+fn load_tileset(path: &Path, first_gid: u32) -> Result<TiledTileset, TiledError> {
+    let source = fs::read_to_string(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    match extension(path).as_deref() {
+        Some("tsx") => tmx::parse_tileset(&source, first_gid, Some(directory)),
+        Some("tsj") | Some("json") => json::parse_tileset(&source, first_gid, Some(directory)),
+        _ => Err(TiledError::Unsupported(format!(
+            "Tileset files like {}",
+            path.display()
+        ))),
+    }
+}
+
+/// The tileset at `source`, relative to `directory`
+fn external_tileset(
+    source: &str,
+    first_gid: u32,
+    directory: Option<&Path>,
+) -> Result<TiledTileset, TiledError> {
+    match directory {
+        Some(directory) => load_tileset(&directory.join(source), first_gid),
+        None => Err(TiledError::Unsupported(format!(
+            "External tileset {} of a map parsed from a string, load the map instead",
+            source
+        ))),
+    }
+}
+
+/// Path of an image relative to `directory`
+fn resolve(image: &str, directory: Option<&Path>) -> PathBuf {
+    match directory {
+        Some(directory) => directory.join(image),
+        None => PathBuf::from(image),
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+}
+
+/// Decode the tiles of a layer stored as CSV or as base64, optionally compressed
+fn decode_tiles(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, TiledError> {
+    match encoding {
+        Some("csv") => data
+            .split(',')
+            .map(|gid| gid.trim())
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse()
+                    .map_err(|_| TiledError::Invalid(format!("Invalid tile {:?}", gid)))
+            })
+            .collect(),
+        Some("base64") => {
+            let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = ::base64::decode(&data)
+                .map_err(|error| TiledError::Invalid(format!("Invalid base64: {}", error)))?;
+            let mut decompressed = vec![];
+            match compression {
+                None | Some("") => decompressed = bytes,
+                Some("zlib") => {
+                    ZlibDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+                }
+                Some("gzip") => {
+                    GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+                }
+                Some(compression) => {
+                    return Err(TiledError::Unsupported(format!(
+                        "{} compressed layers",
+                        compression
+                    )))
+                }
+            }
+            if decompressed.len() % 4 != 0 {
+                return Err(TiledError::Invalid(
+                    "The layer's data is not a list of tiles".to_string(),
+                ));
+            }
+            Ok(decompressed
+                .chunks(4)
+                .map(|bytes| {
+                    u32::from(bytes[0])
+                        | u32::from(bytes[1]) << 8
+                        | u32::from(bytes[2]) << 16
+                        | u32::from(bytes[3]) << 24
+                })
+                .collect())
+        }
+        Some(encoding) => Err(TiledError::Unsupported(format!(
+            "{} encoded layers",
+            encoding
+        ))),
+        None => Err(TiledError::Invalid(
+            "The layer's data has no encoding".to_string(),
+        )),
+    }
+}
+
+/// Parse a color written as `#RRGGBB` or `#AARRGGBB`, an empty string is transparent
+fn parse_color(color: &str) -> Option<Color> {
+    if color.is_empty() {
+        return Some(Color::RGBA(0, 0, 0, 0));
+    }
+    let hex = color.trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let channel = |shift: u32| (value >> shift) as u8;
+    match hex.len() {
+        6 => Some(Color::RGB(channel(16), channel(8), channel(0))),
+        8 => Some(Color::RGBA(
+            channel(16),
+            channel(8),
+            channel(0),
+            channel(24),
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::common::arena::Arena;
+    use super::super::super::testing::TempDirectory;
+    use super::*;
+    use std::time::Duration;
+
+    fn map(layers: &str, objects: &str) -> TiledMap {
+        TiledMap::parse_tmx(&format!(
+            r#"<map width="2" height="1" tilewidth="8" tileheight="8">
+                <tileset firstgid="5" name="terrain" tilewidth="8" tileheight="8" tilecount="4">
+                    <image source="terrain.png" width="32" height="8"/>
+                    <tile id="1">
+                        <animation><frame tileid="2" duration="100"/></animation>
+                    </tile>
+                </tileset>
+                {}
+                <objectgroup name="walls" visible="0">{}</objectgroup>
+            </map>"#,
+            layers, objects
+        ))
+        .unwrap()
+    }
+
+    fn assert_close(actual: &Vector2, expected: &Vector2) {
+        assert!(
+            actual.sub(expected).length() < 1e-3,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_to_tilemap() {
+        let texture = TextureHandle(Arena::new().insert(()));
+        let tiled = map(
+            r#"<layer name="ground"><data encoding="csv">6,2147483656</data></layer>
+               <layer name="hidden" visible="0"><data encoding="csv">0,5</data></layer>"#,
+            "",
+        );
+
+        let tilemap = tiled.to_tilemap(texture).unwrap();
+
+        assert_eq!(tilemap.get_layers().len(), 2);
+        assert_eq!(tilemap.get_tile(0, 0, 0), Some(Tile::new(1)));
+        assert_eq!(
+            tilemap.get_tile(0, 1, 0),
+            Some(Tile::flipped(3, true, false))
+        );
+        assert!(!tilemap.get_layer(1).unwrap().visible);
+        assert_eq!(tilemap.get_tileset().len(), 4);
+        assert_eq!(tilemap.get_tileset().animate(1, &Duration::from_secs(0)), 2);
+
+        let invalid = |layer: &str| map(layer, "").to_tilemap(texture);
+        match invalid(r#"<layer><data encoding="csv">4,0</data></layer>"#) {
+            Err(TiledError::Invalid(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match invalid(r#"<layer><data encoding="csv">536870917,0</data></layer>"#) {
+            Err(TiledError::Unsupported(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_spawn_colliders() {
+        let tiled = map(
+            "",
+            r#"<object id="1" x="0" y="8" width="16" height="8">
+                <properties><property name="trigger" type="bool" value="true"/></properties>
+               </object>
+               <object id="2" x="10" y="10" width="4" height="2" rotation="90"/>
+               <object id="3" x="4" y="4" width="2" height="2"><ellipse/></object>
+               <object id="4" x="0" y="0"><polygon points="0,0 4,0 4,2 0,2"/></object>
+               <object id="5" x="3" y="3"><point/></object>"#,
+        );
+        let mut world = World::new();
+
+        let entities = tiled
+            .spawn_colliders(&mut world, &Vector2::new(100., 0.))
+            .unwrap();
+
+        assert_eq!(entities.len(), 4);
+        let position = |entity: Entity| world.get_component::<Position>(entity).unwrap().0.clone();
+        assert_close(&position(entities[0]), &Vector2::new(108., 12.));
+        assert!(world.has_component::<Trigger>(entities[0]));
+        assert!(!world.has_component::<Trigger>(entities[1]));
+        // Rotated around the top left corner, the box extends to the left
+        assert_close(&position(entities[1]), &Vector2::new(109., 12.));
+        match world.get_component::<Collider>(entities[1]) {
+            Some(&Collider::OBB(ref obb)) => {
+                assert_close(&obb.get_local()[0], &Vector2::new(0., 1.));
+                assert_close(obb.get_extents(), &Vector2::new(2., 1.));
+            }
+            other => panic!("Unexpected collider {:?}", other),
+        }
+        match world.get_component::<Collider>(entities[2]) {
+            Some(&Collider::Circle(ref circle)) => assert_eq!(circle.get_radius(), 1.),
+            other => panic!("Unexpected collider {:?}", other),
+        }
+        assert_close(&position(entities[3]), &Vector2::new(102., 1.));
+        match world.get_component::<Collider>(entities[3]) {
+            Some(&Collider::Polygon(ref corners)) => {
+                assert_eq!(corners.len(), 4);
+                assert_close(&corners[0], &Vector2::new(-2., -1.));
+                assert_close(&corners[2], &Vector2::new(2., 1.));
+            }
+            other => panic!("Unexpected collider {:?}", other),
+        }
+
+        // Nothing is spawned for maps with unsupported colliders
+        let ellipse = map(
+            "",
+            r#"<object id="1" x="0" y="0" width="4" height="4"/>
+               <object id="2" x="0" y="0" width="4" height="2"><ellipse/></object>"#,
+        );
+        match ellipse.spawn_colliders(&mut world, &Vector2::new(0., 0.)) {
+            Err(TiledError::Unsupported(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let concave = map(
+            "",
+            r#"<object id="1" x="0" y="0"><polygon points="0,0 4,0 4,4 2,1 0,4"/></object>"#,
+        );
+        match concave.spawn_colliders(&mut world, &Vector2::new(0., 0.)) {
+            Err(TiledError::Unsupported(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let star = map(
+            "",
+            r#"<object id="1" x="0" y="0"><polygon points="0,-5 3,4 -5,-2 5,-2 -3,4"/></object>"#,
+        );
+        match star.spawn_colliders(&mut world, &Vector2::new(0., 0.)) {
+            Err(TiledError::Unsupported(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(world.entity_count(), 4);
+    }
+
+    #[test]
+    fn test_load_external_tileset() {
+        let temp = TempDirectory::new("tiled_external_tileset");
+        let directory = temp.path();
+        fs::create_dir_all(directory.join("tilesets")).unwrap();
+        fs::write(
+            directory.join("tilesets").join("terrain.tsx"),
+            r#"<tileset name="terrain" tilewidth="8" tileheight="8" tilecount="4">
+                <image source="terrain.png" width="32" height="8"/>
+            </tileset>"#,
+        )
+        .unwrap();
+        let path = directory.join("level.tmx");
+        fs::write(
+            &path,
+            r#"<map width="2" height="2" tilewidth="8" tileheight="8">
+                <tileset firstgid="1" source="tilesets/terrain.tsx"/>
+                <layer><data encoding="base64" compression="gzip">
+                    H4sIAAAAAAACA2NkgAAmBoYGZiANAMXCpaMQAAAA
+                </data></layer>
+            </map>"#,
+        )
+        .unwrap();
+
+        let map = TiledMap::load(&path).unwrap();
+        assert_eq!(
+            map.tilesets[0].image,
+            directory.join("tilesets").join("terrain.png")
+        );
+        match map.layers[0] {
+            TiledLayer::Tiles(ref layer) => assert_eq!(layer.data, vec![1, 0, 0x8000_0002, 3]),
+            _ => panic!("Expected a tile layer"),
+        }
+        match TiledMap::load(directory.join("missing.tmx")) {
+            Err(TiledError::Io(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use xml::reader::{EventReader, XmlEvent};
+
+use super::super::super::common::vector2::Vector2;
+use super::super::render::TileFrame;
+use super::{
+    decode_tiles, external_tileset, resolve, ObjectLayer, ObjectShape, Properties, PropertyValue,
+    TiledError, TiledLayer, TiledMap, TiledObject, TiledTileLayer, TiledTileset,
+};
+
+/// Parse a map in Tiled's TMX format, files are relative to `directory`
+pub fn parse_map(source: &str, directory: Option<&Path>) -> Result<TiledMap, TiledError> {
+    let map = Element::parse(source)?;
+    if map.name != "map" {
+        return Err(TiledError::Invalid(format!(
+            "Expected a map, found {}",
+            map.name
+        )));
+    }
+    let orientation = map.attribute("orientation").unwrap_or("orthogonal");
+    if orientation != "orthogonal" {
+        return Err(TiledError::Unsupported(format!("{} maps", orientation)));
+    }
+    if map.optional("infinite", 0)? != 0 {
+        return Err(TiledError::Unsupported("Infinite maps".to_string()));
+    }
+    let size = (map.required("width")?, map.required("height")?);
+    let mut tilesets = vec![];
+    let mut layers = vec![];
+    for child in map.children.iter() {
+        match child.name.as_str() {
+            "tileset" => {
+                let first_gid = child.required("firstgid")?;
+                tilesets.push(match child.attribute("source") {
+                    Some(source) => external_tileset(source, first_gid, directory)?,
+                    None => convert_tileset(child, first_gid, directory)?,
+                });
+            }
+            "layer" => layers.push(convert_tile_layer(child, size)?),
+            "objectgroup" => layers.push(convert_object_layer(child)?),
+            "properties" | "editorsettings" => {}
+            name => {
+                return Err(TiledError::Unsupported(format!(
+                    "{} elements, like {}",
+                    name,
+                    child.attribute("name").unwrap_or("")
+                )))
+            }
+        }
+    }
+    Ok(TiledMap {
+        size: size,
+        tile_size: (map.required("tilewidth")?, map.required("tileheight")?),
+        properties: convert_properties(&map)?,
+        tilesets: tilesets,
+        layers: layers,
+    })
+}
+
+/// Parse a tileset in Tiled's TSX format, its image is relative to `directory`
+pub fn parse_tileset(
+    source: &str,
+    first_gid: u32,
+    directory: Option<&Path>,
+) -> Result<TiledTileset, TiledError> {
+    convert_tileset(&Element::parse(source)?, first_gid, directory)
+}
+
+fn convert_tileset(
+    tileset: &Element,
+    first_gid: u32,
+    directory: Option<&Path>,
+) -> Result<TiledTileset, TiledError> {
+    let name = tileset.attribute("name").unwrap_or("").to_string();
+    let image = match tileset.child("image") {
+        Some(image) => image,
+        None => {
+            return Err(TiledError::Unsupported(format!(
+                "Image collection tileset {}",
+                name
+            )))
+        }
+    };
+    let source = image
+        .attribute("source")
+        .ok_or_else(|| TiledError::Invalid(format!("The image of {} has no source", name)))?;
+    let mut animations = HashMap::new();
+    let mut tile_properties = HashMap::new();
+    for tile in tileset.children("tile") {
+        let id = tile.required("id")?;
+        if let Some(animation) = tile.child("animation") {
+            let frames = animation
+                .children("frame")
+                .map(|frame| {
+                    Ok(TileFrame {
+                        index: frame.required("tileid")?,
+                        duration: Duration::from_millis(frame.required("duration")?),
+                    })
+                })
+                .collect::<Result<Vec<TileFrame>, TiledError>>()?;
+            animations.insert(id, frames);
+        }
+        let properties = convert_properties(tile)?;
+        if !properties.is_empty() {
+            tile_properties.insert(id, properties);
+        }
+    }
+    Ok(TiledTileset {
+        first_gid: first_gid,
+        image: resolve(source, directory),
+        image_size: (image.optional("width", 0)?, image.optional("height", 0)?),
+        tile_size: (
+            tileset.required("tilewidth")?,
+            tileset.required("tileheight")?,
+        ),
+        margin: tileset.optional("margin", 0)?,
+        spacing: tileset.optional("spacing", 0)?,
+        tile_count: tileset.optional("tilecount", 0)?,
+        animations: animations,
+        tile_properties: tile_properties,
+        properties: convert_properties(tileset)?,
+        name: name,
+    })
+}
+
+fn convert_tile_layer(layer: &Element, size: (u32, u32)) -> Result<TiledLayer, TiledError> {
+    let name = layer.attribute("name").unwrap_or("").to_string();
+    let data = layer
+        .child("data")
+        .ok_or_else(|| TiledError::Invalid(format!("Layer {} has no data", name)))?;
+    let tiles = match data.attribute("encoding") {
+        // Tiles stored as elements
+        None => data
+            .children("tile")
+            .map(|tile| tile.optional("gid", 0))
+            .collect::<Result<Vec<u32>, TiledError>>()?,
+        encoding => decode_tiles(&data.text, encoding, data.attribute("compression"))?,
+    };
+    if tiles.len() != (size.0 * size.1) as usize {
+        return Err(TiledError::Invalid(format!(
+            "Layer {} is not the size of the map",
+            name
+        )));
+    }
+    Ok(TiledLayer::Tiles(TiledTileLayer {
+        visible: layer.optional("visible", 1)? != 0,
+        properties: convert_properties(layer)?,
+        data: tiles,
+        name: name,
+    }))
+}
+
+fn convert_object_layer(layer: &Element) -> Result<TiledLayer, TiledError> {
+    Ok(TiledLayer::Objects(ObjectLayer {
+        name: layer.attribute("name").unwrap_or("").to_string(),
+        visible: layer.optional("visible", 1)? != 0,
+        properties: convert_properties(layer)?,
+        objects: layer
+            .children("object")
+            .map(convert_object)
+            .collect::<Result<Vec<TiledObject>, TiledError>>()?,
+    }))
+}
+
+fn convert_object(object: &Element) -> Result<TiledObject, TiledError> {
+    let id = object.optional("id", 0)?;
+    let points = |element: &Element| {
+        element
+            .attribute("points")
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|point| {
+                let mut coordinates = point.split(',').map(f32::from_str);
+                match (coordinates.next(), coordinates.next()) {
+                    (Some(Ok(x)), Some(Ok(y))) => Ok(Vector2::new(x, y)),
+                    _ => Err(TiledError::Invalid(format!(
+                        "Invalid point {:?} of object {}",
+                        point, id
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<Vector2>, TiledError>>()
+    };
+    let shape = if object.child("text").is_some() || object.attribute("gid").is_some() {
+        return Err(TiledError::Unsupported(format!(
+            "Text and tile objects, like object {}",
+            id
+        )));
+    } else if object.child("ellipse").is_some() {
+        ObjectShape::Ellipse
+    } else if object.child("point").is_some() {
+        ObjectShape::Point
+    } else if let Some(polygon) = object.child("polygon") {
+        ObjectShape::Polygon(points(polygon)?)
+    } else if let Some(polyline) = object.child("polyline") {
+        ObjectShape::Polyline(points(polyline)?)
+    } else {
+        ObjectShape::Rectangle
+    };
+    let class = object
+        .attribute("class")
+        .or_else(|| object.attribute("type"))
+        .unwrap_or("");
+    Ok(TiledObject {
+        id: id,
+        name: object.attribute("name").unwrap_or("").to_string(),
+        class: class.to_string(),
+        position: Vector2::new(object.optional("x", 0.)?, object.optional("y", 0.)?),
+        width: object.optional("width", 0.)?,
+        height: object.optional("height", 0.)?,
+        rotation: object.optional("rotation", 0.)?,
+        visible: object.optional("visible", 1)? != 0,
+        shape: shape,
+        properties: convert_properties(object)?,
+    })
+}
+
+/// The properties of the `properties` child of `element`
+fn convert_properties(element: &Element) -> Result<Properties, TiledError> {
+    let properties = match element.child("properties") {
+        Some(properties) => properties,
+        None => return Ok(HashMap::new()),
+    };
+    properties
+        .children("property")
+        .map(|property| {
+            let name = property.attribute("name").unwrap_or("").to_string();
+            let kind = property.attribute("type").unwrap_or("string");
+            // Multiline strings are stored as the text of the property
+            let value = property.attribute("value").unwrap_or(&property.text);
+            Ok((name, PropertyValue::parse(kind, value)?))
+        })
+        .collect()
+}
+
+/// A node of an XML document, without its comments and processing instructions
+#[derive(Debug)]
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    /// Parse the root element of `source`
+    fn parse(source: &str) -> Result<Element, TiledError> {
+        let mut stack: Vec<Element> = vec![];
+        for event in EventReader::from_str(source) {
+            match event? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(Element {
+                    name: name.local_name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|attribute| (attribute.name.local_name, attribute.value))
+                        .collect(),
+                    children: vec![],
+                    text: String::new(),
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let element = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(TiledError::Invalid("The document is empty".to_string()))
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(|value| value.as_str())
+    }
+
+    fn required<T>(&self, name: &str) -> Result<T, TiledError>
+    where
+        T: FromStr,
+    {
+        match self.attribute(name) {
+            Some(value) => parse(&self.name, name, value),
+            None => Err(TiledError::Invalid(format!(
+                "{} is missing {}",
+                self.name, name
+            ))),
+        }
+    }
+
+    fn optional<T>(&self, name: &str, default: T) -> Result<T, TiledError>
+    where
+        T: FromStr,
+    {
+        match self.attribute(name) {
+            Some(value) => parse(&self.name, name, value),
+            None => Ok(default),
+        }
+    }
+
+    /// The first child named `name`
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+fn parse<T>(element: &str, name: &str, value: &str) -> Result<T, TiledError>
+where
+    T: FromStr,
+{
+    value
+        .parse()
+        .map_err(|_| TiledError::Invalid(format!("Invalid {} {:?} of {}", name, value, element)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::render::Color;
+    use super::*;
+
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="8" tileheight="8" infinite="0">
+ <properties>
+  <property name="gravity" type="float" value="9.5"/>
+  <property name="sky" type="color" value="#ff336699"/>
+  <property name="intro">Hello
+world</property>
+ </properties>
+ <tileset firstgid="1" name="terrain" tilewidth="8" tileheight="8" tilecount="4" columns="4">
+  <image source="terrain.png" width="32" height="8"/>
+  <tile id="1">
+   <properties>
+    <property name="solid" type="bool" value="true"/>
+   </properties>
+   <animation>
+    <frame tileid="1" duration="100"/>
+    <frame tileid="2" duration="200"/>
+   </animation>
+  </tile>
+ </tileset>
+ <layer id="1" name="ground" width="2" height="2">
+  <data encoding="csv">
+1,0,
+2147483651,4
+</data>
+ </layer>
+ <layer id="2" name="top" width="2" height="2" visible="0">
+  <data encoding="base64" compression="zlib">
+   eJxjZIAAJgaGBmYgDQACvACH
+  </data>
+ </layer>
+ <objectgroup id="3" name="walls">
+  <object id="1" name="floor" type="wall" x="0" y="8" width="16" height="8"/>
+  <object id="2" class="coin" x="4" y="4" width="2" height="2"><ellipse/></object>
+  <object id="3" x="1" y="2" rotation="90"><polygon points="0,0 4,0 4,-2.5"/></object>
+  <object id="4" x="3" y="3"><point/></object>
+ </objectgroup>
+</map>
+"##;
+
+    #[test]
+    fn test_parse_map() {
+        let map = TiledMap::parse_tmx(MAP).unwrap();
+
+        assert_eq!((map.size, map.tile_size), ((2, 2), (8, 8)));
+        assert_eq!(map.properties["gravity"], PropertyValue::Float(9.5));
+        assert_eq!(
+            map.properties["sky"],
+            PropertyValue::Color(Color::RGBA(0x33, 0x66, 0x99, 0xff))
+        );
+        assert_eq!(
+            map.properties["intro"],
+            PropertyValue::String("Hello\nworld".to_string())
+        );
+
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.image, Path::new("terrain.png"));
+        assert_eq!((tileset.image_size, tileset.tile_count), ((32, 8), 4));
+        assert_eq!(
+            tileset.animations[&1][1].duration,
+            Duration::from_millis(200)
+        );
+        assert_eq!(
+            tileset.tile_properties[&1]["solid"],
+            PropertyValue::Bool(true)
+        );
+
+        match (&map.layers[0], &map.layers[1]) {
+            (&TiledLayer::Tiles(ref ground), &TiledLayer::Tiles(ref top)) => {
+                assert_eq!(ground.data, vec![1, 0, 0x8000_0003, 4]);
+                assert_eq!(top.data, vec![1, 0, 0x8000_0002, 3]);
+                assert!(ground.visible && !top.visible);
+            }
+            _ => panic!("Expected two tile layers"),
+        }
+
+        let objects = map.objects();
+        assert_eq!(objects.len(), 4);
+        assert_eq!(
+            (objects[0].name.as_str(), objects[0].class.as_str()),
+            ("floor", "wall")
+        );
+        assert_eq!(objects[0].shape, ObjectShape::Rectangle);
+        assert_eq!(
+            (objects[1].class.as_str(), &objects[1].shape),
+            ("coin", &ObjectShape::Ellipse)
+        );
+        assert_eq!(
+            objects[2].shape,
+            ObjectShape::Polygon(vec![
+                Vector2::new(0., 0.),
+                Vector2::new(4., 0.),
+                Vector2::new(4., -2.5),
+            ])
+        );
+        assert_eq!(objects[2].rotation, 90.);
+        assert_eq!(objects[3].shape, ObjectShape::Point);
+    }
+
+    #[test]
+    fn test_unsupported_features_are_reported() {
+        let unsupported = |map: &str| match TiledMap::parse_tmx(map) {
+            Err(TiledError::Unsupported(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        };
+
+        unsupported(
+            r#"<map orientation="isometric" width="1" height="1" tilewidth="8" tileheight="8"/>"#,
+        );
+        unsupported(r#"<map width="1" height="1" tilewidth="8" tileheight="8" infinite="1"/>"#);
+        unsupported(
+            r#"<map width="1" height="1" tilewidth="8" tileheight="8"><imagelayer name="sky"/></map>"#,
+        );
+        unsupported(
+            r#"<map width="1" height="1" tilewidth="8" tileheight="8"><tileset firstgid="1" source="terrain.tsx"/></map>"#,
+        );
+        unsupported(
+            r#"<map width="1" height="1" tilewidth="8" tileheight="8">
+                <objectgroup><object id="1"><text>Hi</text></object></objectgroup>
+            </map>"#,
+        );
+        match TiledMap::parse_tmx("<map") {
+            Err(TiledError::Xml(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
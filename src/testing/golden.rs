@@ -0,0 +1,276 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::super::systems::render::{AssetError, Image, RenderSystem};
+
+/// Set to save the rendered images as the new references instead of comparing them
+pub const UPDATE_GOLDEN_VAR: &str = "BLIGHT_UPDATE_GOLDEN";
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Asset(AssetError),
+    /// The rendered image and the reference have different sizes
+    Size {
+        actual: (u32, u32),
+        expected: (u32, u32),
+    },
+    /// Pixels differ from the reference by more than the tolerance
+    /// The rendered image and a diff marking the pixels in red are saved next to the reference
+    Mismatch {
+        pixels: usize,
+        max_difference: u8,
+        diff: PathBuf,
+    },
+}
+
+impl From<AssetError> for GoldenError {
+    fn from(error: AssetError) -> Self {
+        GoldenError::Asset(error)
+    }
+}
+
+/// Pixel by pixel comparison of two images of the same size
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageDiff {
+    /// Number of pixels with a channel differing by more than the tolerance
+    pub mismatched: usize,
+    /// Largest difference of a channel over the whole image
+    pub max_difference: u8,
+    /// The expected image dimmed, with the mismatched pixels in red
+    pub image: Image,
+}
+
+impl ImageDiff {
+    /// Compare `actual` to `expected`, channels differing by at most `tolerance` match
+    pub fn new(actual: &Image, expected: &Image, tolerance: u8) -> Result<ImageDiff, GoldenError> {
+        if (actual.width, actual.height) != (expected.width, expected.height) {
+            return Err(GoldenError::Size {
+                actual: (actual.width, actual.height),
+                expected: (expected.width, expected.height),
+            });
+        }
+        let mut mismatched = 0;
+        let mut max_difference = 0;
+        let mut pixels = Vec::with_capacity(expected.pixels.len());
+        for (actual, expected) in actual.pixels.chunks(4).zip(expected.pixels.chunks(4)) {
+            let difference = actual
+                .iter()
+                .zip(expected.iter())
+                .map(|(a, e)| a.max(e) - a.min(e))
+                .max()
+                .unwrap_or(0);
+            max_difference = max_difference.max(difference);
+            if difference > tolerance {
+                mismatched += 1;
+                pixels.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                let gray =
+                    (u16::from(expected[0]) + u16::from(expected[1]) + u16::from(expected[2])) / 12;
+                pixels.extend_from_slice(&[gray as u8, gray as u8, gray as u8, 255]);
+            }
+        }
+        Ok(ImageDiff {
+            mismatched: mismatched,
+            max_difference: max_difference,
+            image: Image {
+                width: expected.width,
+                height: expected.height,
+                pixels: pixels,
+            },
+        })
+    }
+}
+
+/// Render a single frame offscreen and read it back
+/// `draw` sets up the components of the frame on a headless `RenderSystem` of `size`
+pub fn render_offscreen<F>(size: (u32, u32), draw: F) -> Result<Image, AssetError>
+where
+    F: FnOnce(&mut RenderSystem),
+{
+    let mut render_system = RenderSystem::offscreen(size);
+    draw(&mut render_system);
+    render_system.capture_next_frame();
+    render_system.render();
+    render_system
+        .take_captured_frame()
+        .unwrap_or_else(|| Err(AssetError::Sdl("The frame was not captured".to_string())))
+}
+
+/// Compare `actual` to the reference PNG at `path`, channels differing by at most `tolerance`
+/// match
+/// On failure the image is saved next to the reference with the `actual.png` extension, with
+/// a `diff.png` if the sizes match
+/// When `BLIGHT_UPDATE_GOLDEN` is set the image is saved as the new reference instead
+pub fn check_golden<P>(actual: &Image, path: P, tolerance: u8) -> Result<(), GoldenError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(AssetError::from)?;
+        }
+        return Ok(actual.save(path)?);
+    }
+    let compared = Image::load_png(path)
+        .map_err(GoldenError::from)
+        .and_then(|expected| ImageDiff::new(actual, &expected, tolerance));
+    let diff = match compared {
+        Ok(ref diff) if diff.mismatched == 0 => return Ok(()),
+        Ok(diff) => diff,
+        Err(error) => {
+            actual.save(path.with_extension("actual.png"))?;
+            return Err(error);
+        }
+    };
+    let diff_path = path.with_extension("diff.png");
+    actual.save(path.with_extension("actual.png"))?;
+    diff.image.save(&diff_path)?;
+    Err(GoldenError::Mismatch {
+        pixels: diff.mismatched,
+        max_difference: diff.max_difference,
+        diff: diff_path,
+    })
+}
+
+/// Panic unless `actual` matches the reference PNG at `path`, see `check_golden`
+pub fn assert_golden<P>(actual: &Image, path: P, tolerance: u8)
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if let Err(error) = check_golden(actual, path, tolerance) {
+        panic!(
+            "{} does not match the rendered image: {:?}",
+            path.display(),
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::common::aabb::AABB;
+    use super::super::super::common::vector2::Vector2;
+    use super::super::super::systems::render::{Color, Rect, Space};
+    use super::super::TempDirectory;
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> Image {
+        Image {
+            width: pixels.len() as u32,
+            height: 1,
+            pixels: pixels.iter().flat_map(|pixel| pixel.to_vec()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_marks_pixels_beyond_the_tolerance() {
+        let expected = image(&[[120, 120, 120, 255], [0, 0, 0, 255], [9, 9, 9, 255]]);
+        let actual = image(&[[122, 120, 119, 255], [0, 40, 0, 255], [9, 9, 9, 255]]);
+
+        let diff = ImageDiff::new(&actual, &expected, 2).unwrap();
+
+        assert_eq!(diff.mismatched, 1);
+        assert_eq!(diff.max_difference, 40);
+        assert_eq!(
+            diff.image.pixels,
+            vec![30, 30, 30, 255, 255, 0, 0, 255, 2, 2, 2, 255]
+        );
+        match ImageDiff::new(&image(&[[0, 0, 0, 0]]), &expected, 0) {
+            Err(GoldenError::Size { actual, expected }) => {
+                assert_eq!((actual, expected), ((1, 1), (3, 1)))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_golden_saves_the_diff_of_mismatches() {
+        let temp = TempDirectory::new("golden");
+        let directory = temp.path();
+        let path = directory.join("scene.png");
+        let expected = image(&[[10, 20, 30, 255], [40, 50, 60, 255]]);
+        expected.save(&path).unwrap();
+
+        assert!(check_golden(&expected, &path, 0).is_ok());
+        let actual = image(&[[10, 20, 30, 255], [90, 50, 60, 255]]);
+        let result = check_golden(&actual, &path, 0);
+
+        let diff_path = directory.join("scene.diff.png");
+        match result {
+            Err(GoldenError::Mismatch { pixels, diff, .. }) => {
+                assert_eq!((pixels, diff), (1, diff_path.clone()))
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let saved = Image::load_png(directory.join("scene.actual.png")).unwrap();
+        assert_eq!(saved, actual);
+        assert_eq!(
+            Image::load_png(&diff_path).unwrap().pixels[4..],
+            [255, 0, 0, 255]
+        );
+        match check_golden(&actual, directory.join("missing.png"), 0) {
+            Err(GoldenError::Asset(AssetError::Io(_))) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_render_offscreen() {
+        let frame = render_offscreen((4, 3), |render_system| {
+            render_system.set_background_color(Some(Color::RGB(10, 20, 30)));
+        })
+        .unwrap();
+
+        assert_eq!((frame.width, frame.height), (4, 3));
+        assert!(frame
+            .pixels
+            .chunks(4)
+            .all(|pixel| pixel == [10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn test_sprite_scene_matches_the_reference() {
+        let frame = render_offscreen((16, 12), |render_system| {
+            render_system.set_background_color(Some(Color::RGB(20, 20, 40)));
+            let checker = Image {
+                width: 2,
+                height: 2,
+                pixels: vec![
+                    255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255,
+                ],
+            };
+            let texture = render_system.create_texture_from_image(&checker).unwrap();
+            let texture = render_system.add_texture(texture);
+            let sprite = render_system.create_component().release();
+            {
+                let sprite = render_system.try_get_mut(sprite).unwrap();
+                sprite.texture = Some(texture);
+                sprite.position = Rect::new(2, 2, 8, 8);
+            }
+            let primitives = render_system.get_primitives_mut();
+            primitives.set_space(Space::Screen);
+            primitives.fill(
+                &AABB::new(Vector2::new(13., 3.), 4., 4.),
+                Color::RGB(255, 255, 0),
+            );
+        })
+        .unwrap();
+
+        let pixel = |x: u32, y: u32| {
+            let start = ((y * frame.width + x) * 4) as usize;
+            &frame.pixels[start..start + 4]
+        };
+        assert_eq!(pixel(0, 0), [20, 20, 40, 255]);
+        assert_eq!(pixel(3, 3), [255, 0, 0, 255]);
+        assert_eq!(pixel(8, 8), [255, 255, 255, 255]);
+        assert_eq!(pixel(13, 3), [255, 255, 0, 255]);
+        assert_golden(
+            &frame,
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/sprite_scene.png"),
+            0,
+        );
+    }
+}
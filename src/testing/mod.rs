@@ -0,0 +1,349 @@
+//! Utilities for testing `Game` implementations without a window
+//! Rendered frames can be compared with reference images, see `assert_golden`
+use super::core::{BlightCore, BlightCoreBuilder};
+use super::systems::input::Event;
+use super::systems::render::{AssetError, Image};
+use super::Game;
+
+mod golden;
+pub use self::golden::{
+    assert_golden, check_golden, render_offscreen, GoldenError, ImageDiff, UPDATE_GOLDEN_VAR,
+};
+
+/// Owns a headless engine and a game, driving the game one fixed update at a time
+///```no_run
+/// use blight::core::EngineContext;
+/// use blight::testing::GameHarness;
+/// use blight::Game;
+///
+/// struct Counter {
+///     updates: u32,
+/// }
+///
+/// impl Game for Counter {
+///     fn init(_ctx: &mut EngineContext) -> Self {
+///         Counter { updates: 0 }
+///     }
+///
+///     fn update(&mut self, _ctx: &mut EngineContext) {
+///         self.updates += 1;
+///     }
+/// }
+///
+/// let mut harness = GameHarness::<Counter>::new();
+/// harness.step(10);
+/// assert_eq!(harness.game().updates, 10);
+///```
+pub struct GameHarness<TGame>
+where
+    TGame: Game,
+{
+    game: TGame,
+    engine: BlightCore,
+}
+
+impl<TGame> GameHarness<TGame>
+where
+    TGame: Game,
+{
+    /// Create the game on a headless engine
+    pub fn new() -> Self {
+        GameHarness::from_builder(BlightCoreBuilder::new().headless())
+    }
+
+    /// Create the game on an engine configured by `builder`
+    pub fn from_builder(builder: BlightCoreBuilder) -> Self {
+        let mut engine = builder.build().expect("Invalid engine config");
+        engine.start();
+        let game = TGame::init(engine.get_context_mut());
+        GameHarness {
+            game: game,
+            engine: engine,
+        }
+    }
+
+    /// Queue an event for the next update
+    pub fn push_event(&mut self, event: Event) -> &mut Self {
+        self.engine
+            .get_context_mut()
+            .get_input_mut()
+            .push_event(event);
+        self
+    }
+
+    /// Run `updates` fixed updates, then render a frame
+    /// Does nothing once the engine is stopped, e.g. after the game quit
+    pub fn step(&mut self, updates: usize) -> &mut Self {
+        self.engine.step(&mut self.game, updates);
+        self
+    }
+
+    /// Run a single update handling `events`
+    pub fn step_with_events<I>(&mut self, events: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        for event in events {
+            self.push_event(event);
+        }
+        self.step(1)
+    }
+
+    /// Render a frame without updating and read it back, e.g. to compare it with `assert_golden`
+    pub fn capture_frame(&mut self) -> Result<Image, AssetError> {
+        self.engine
+            .get_context_mut()
+            .get_render_mut()
+            .capture_next_frame();
+        self.step(0);
+        self.engine
+            .get_context_mut()
+            .get_render_mut()
+            .take_captured_frame()
+            .unwrap_or_else(|| Err(AssetError::Sdl("The frame was not captured".to_string())))
+    }
+
+    pub fn game(&self) -> &TGame {
+        &self.game
+    }
+
+    pub fn game_mut(&mut self) -> &mut TGame {
+        &mut self.game
+    }
+
+    pub fn engine(&self) -> &BlightCore {
+        &self.engine
+    }
+
+    pub fn engine_mut(&mut self) -> &mut BlightCore {
+        &mut self.engine
+    }
+}
+
+/// Directory of a single test, removed with its contents when dropped
+/// Named after the test and the process, so parallel test runs do not share it
+#[cfg(test)]
+pub(crate) struct TempDirectory(::std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDirectory {
+    pub fn new(name: &str) -> Self {
+        let path =
+            ::std::env::temp_dir().join(format!("blight_test_{}_{}", name, ::std::process::id()));
+        ::std::fs::create_dir_all(&path).unwrap();
+        TempDirectory(path)
+    }
+
+    pub fn path(&self) -> &::std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDirectory {
+    fn drop(&mut self) {
+        let _ = ::std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::core::EngineContext;
+    use super::super::systems::input::Keycode;
+    use super::super::systems::render::WINDOW_SIZE;
+    use super::*;
+
+    struct SpaceCounter {
+        updates: u32,
+        presses: u32,
+    }
+
+    impl Game for SpaceCounter {
+        fn init(_ctx: &mut EngineContext) -> Self {
+            SpaceCounter {
+                updates: 0,
+                presses: 0,
+            }
+        }
+
+        fn update(&mut self, ctx: &mut EngineContext) {
+            self.updates += 1;
+            let presses = ctx
+                .get_input()
+                .iter_events()
+                .filter(|event| {
+                    matches!(
+                        event,
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Space),
+                            ..
+                        }
+                    )
+                })
+                .count();
+            self.presses += presses as u32;
+        }
+    }
+
+    struct Stubborn {
+        quit_requests: u32,
+    }
+
+    impl Game for Stubborn {
+        fn init(_ctx: &mut EngineContext) -> Self {
+            Stubborn { quit_requests: 0 }
+        }
+
+        fn on_quit_request(&mut self, _ctx: &mut EngineContext) -> bool {
+            self.quit_requests += 1;
+            false
+        }
+    }
+
+    fn key_down(keycode: Keycode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            keymod: ::sdl2::keyboard::NOMOD,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn test_step_runs_exact_number_of_updates() {
+        let mut harness = GameHarness::<SpaceCounter>::new();
+
+        harness.step(3).step(4);
+
+        assert_eq!(harness.game().updates, 7);
+        assert!(harness.engine().is_running());
+    }
+
+    #[test]
+    fn test_injected_events_are_seen_by_the_next_update_only() {
+        let mut harness = GameHarness::<SpaceCounter>::new();
+
+        harness
+            .step_with_events(vec![key_down(Keycode::Space), key_down(Keycode::Space)])
+            .step(5);
+
+        assert_eq!(harness.game().presses, 2);
+        assert_eq!(harness.game().updates, 6);
+    }
+
+    #[test]
+    fn test_steps_are_recorded_in_frame_stats() {
+        let mut harness = GameHarness::<SpaceCounter>::new();
+
+        harness.step(3).step(2);
+
+        let stats = harness.engine().get_context().get_frame_stats();
+        assert_eq!(stats.frame_count(), 2);
+        assert_eq!(stats.update_count(), 5);
+        assert_eq!(stats.last_frame().unwrap().updates, 2);
+    }
+
+    #[test]
+    fn test_debug_keys_pause_and_queue_steps() {
+        let builder = BlightCoreBuilder::new()
+            .headless()
+            .pause_key(Keycode::P)
+            .step_key(Keycode::N);
+        let mut harness = GameHarness::<SpaceCounter>::from_builder(builder);
+
+        harness.step_with_events(vec![key_down(Keycode::P), key_down(Keycode::N)]);
+
+        let context = harness.engine_mut().get_context_mut();
+        assert!(context.is_paused());
+        assert_eq!(context.take_queued_steps(), 1);
+    }
+
+    #[test]
+    fn test_screenshot_key_saves_the_next_frame() {
+        let temp = TempDirectory::new("screenshot_key");
+        let directory = temp.path().join("screenshots");
+        let builder = BlightCoreBuilder::new()
+            .headless()
+            .screenshot_key(Keycode::F12, directory.clone());
+        let mut harness = GameHarness::<SpaceCounter>::from_builder(builder);
+
+        harness.step_with_events(vec![key_down(Keycode::F12)]);
+
+        let render = harness.engine().get_context().get_render();
+        let saved = render.get_saved_screenshots();
+        assert_eq!(saved.len(), 1);
+        assert!(saved[0].0.starts_with(&directory));
+        assert_eq!(saved[0].0.extension().unwrap(), "png");
+        assert!(saved[0].1.is_ok());
+        let image = Image::load_png(&saved[0].0).unwrap();
+        assert_eq!((image.width, image.height), WINDOW_SIZE);
+    }
+
+    #[test]
+    fn test_capture_frame_does_not_update() {
+        let mut harness = GameHarness::<SpaceCounter>::new();
+
+        let frame = harness.capture_frame().unwrap();
+
+        assert_eq!(
+            frame.pixels.len(),
+            (frame.width * frame.height * 4) as usize
+        );
+        assert_eq!(harness.game().updates, 0);
+    }
+
+    #[test]
+    fn test_escape_stops_stepping() {
+        let mut harness = GameHarness::<SpaceCounter>::new();
+
+        harness.push_event(key_down(Keycode::Escape)).step(10);
+
+        assert_eq!(harness.game().updates, 1);
+        assert!(!harness.engine().is_running());
+    }
+
+    #[test]
+    fn test_stepping_does_not_resume_a_stopped_engine() {
+        let mut harness = GameHarness::<SpaceCounter>::new();
+
+        harness
+            .step_with_events(vec![key_down(Keycode::Escape)])
+            .step(3);
+
+        assert_eq!(harness.game().updates, 1);
+        assert!(!harness.engine().is_running());
+        assert_eq!(
+            harness
+                .engine()
+                .get_context()
+                .get_frame_stats()
+                .frame_count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_game_can_veto_quitting() {
+        let mut harness = GameHarness::<Stubborn>::new();
+
+        harness
+            .step_with_events(vec![key_down(Keycode::Escape)])
+            .step_with_events(vec![Event::Quit { timestamp: 0 }]);
+
+        assert_eq!(harness.game().quit_requests, 2);
+        assert!(harness.engine().is_running());
+    }
+
+    #[test]
+    fn test_quit_key_can_be_disabled() {
+        let builder = BlightCoreBuilder::new().headless().quit_key(None);
+        let mut harness = GameHarness::<SpaceCounter>::from_builder(builder);
+
+        harness.push_event(key_down(Keycode::Escape)).step(2);
+
+        assert_eq!(harness.game().updates, 2);
+        assert!(harness.engine().is_running());
+    }
+}